@@ -120,8 +120,15 @@ fn main() {
 		}
 	}
 
+	// window settings can be changed by creating a settings.ron file
+	let config = isopod::RunConfig::from_file("settings.ron").unwrap_or(isopod::RunConfig {
+		title: "demo scene".into(),
+		high_dpi: true,
+		..Default::default()
+	});
+
 	// run engine
-	isopod::run(|c| {
+	isopod::run(config, |c| {
 		SceneDemo {
 			dragon_mesh: c.gfx.register_mesh(Mesh::U32(dragon_mesh)),
 			floor_mesh: c.gfx.register_mesh(Mesh::U32(floor_mesh)),
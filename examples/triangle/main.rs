@@ -35,7 +35,7 @@ impl isopod::App for TriangleDemo {
 }
 
 fn main() {
-	isopod::run(|c| {
+	isopod::run(isopod::RunConfig { title: "triangle".into(), ..Default::default() }, |c| {
 		TriangleDemo {
 			triangle_shader: c.gfx.register_shader(ShaderDefinition {
 				code: include_str!("shader.txt").into(),
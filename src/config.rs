@@ -0,0 +1,121 @@
+use std::path::{Path, PathBuf};
+
+/// How the window is displayed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowMode {
+	#[default]
+	Windowed,
+	/// A window without decorations such as a title bar or border.
+	Borderless,
+	/// Exclusive fullscreen, which changes the display mode of the monitor to match the window size.
+	Fullscreen,
+	/// A borderless window covering the whole monitor without changing its display mode.
+	FullscreenDesktop,
+}
+
+/// How rendered frames are presented to the window.
+///
+/// Modes that are not supported by the GPU fall back to [`VSync`](PresentMode::VSync), which is always supported.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PresentMode {
+	/// Waits for the display to refresh before presenting, which limits the frame rate to the refresh rate and prevents tearing.
+	#[default]
+	VSync,
+	/// Like [`VSync`](PresentMode::VSync), but presents immediately if a frame is late, which can cause tearing.
+	AdaptiveVSync,
+	/// Renders as fast as possible but only presents the latest frame on refresh, which prevents tearing.
+	Mailbox,
+	/// Presents frames as soon as they are rendered, which can cause tearing.
+	Immediate,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+	#[error("failed to access \"{}\"", path.display())]
+	IoError {
+		path: PathBuf,
+		#[source]
+		source: std::io::Error,
+	},
+	#[error("failed to parse config")]
+	ParseError(#[from] ron::error::SpannedError),
+	#[error("failed to serialize config")]
+	SerializeError(#[from] ron::Error),
+}
+
+/// Options used to start the engine with [`run`](crate::run).
+///
+/// Can be loaded from a RON file so that players can change settings such as the window mode.
+/// Missing fields use their default values.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct RunConfig {
+	pub title: String,
+	/// Initial size of the window in screen coordinates.
+	pub size: (u32, u32),
+	pub min_size: Option<(u32, u32)>,
+	pub max_size: Option<(u32, u32)>,
+	pub resizable: bool,
+	pub window_mode: WindowMode,
+	pub present_mode: PresentMode,
+	/// Whether to render at the full resolution of high DPI displays.
+	///
+	/// When disabled, the window is rendered at a lower resolution and scaled up by the system on these displays.
+	pub high_dpi: bool,
+	/// See [`EngineCtx::set_target_fps`](crate::EngineCtx::set_target_fps).
+	pub target_fps: Option<f64>,
+	/// See [`EngineCtx::set_unfocused_fps`](crate::EngineCtx::set_unfocused_fps).
+	pub unfocused_fps: Option<f64>,
+	/// See [`EngineCtx::set_dt_smoothing`](crate::EngineCtx::set_dt_smoothing).
+	pub smooth_dt: bool,
+}
+
+impl Default for RunConfig {
+	fn default() -> Self {
+		Self {
+			title: String::new(),
+			size: (800, 800),
+			min_size: None,
+			max_size: None,
+			resizable: true,
+			window_mode: WindowMode::Windowed,
+			present_mode: PresentMode::VSync,
+			high_dpi: false,
+			target_fps: None,
+			unfocused_fps: None,
+			smooth_dt: false,
+		}
+	}
+}
+
+impl RunConfig {
+	/// Parses a config from RON.
+	pub fn from_ron(ron: &str) -> Result<Self, ConfigError> {
+		Ok(ron::de::from_str(ron)?)
+	}
+
+	/// Serializes this config as human readable RON.
+	pub fn to_ron(&self) -> Result<String, ConfigError> {
+		Ok(ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?)
+	}
+
+	/// Loads a config from a RON file on disk.
+	pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+		Self::from_ron(&read_file(path.as_ref())?)
+	}
+
+	/// Saves this config to a RON file on disk.
+	pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ConfigError> {
+		write_file(path.as_ref(), &self.to_ron()?)
+	}
+}
+
+fn read_file(path: &Path) -> Result<String, ConfigError> {
+	std::fs::read_to_string(path).map_err(|source| ConfigError::IoError { path: path.to_path_buf(), source })
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), ConfigError> {
+	std::fs::write(path, contents).map_err(|source| ConfigError::IoError { path: path.to_path_buf(), source })
+}
@@ -33,6 +33,18 @@ impl Vertex {
 #[serde(rename_all = "snake_case")] 
 enum Cmd {
 	Msg(String),
+	/// Lists every loaded resource.
+	Resources,
+	/// Records input to a file.
+	Record(String),
+	/// Plays back input recorded to a file.
+	Replay(String),
+	/// Stops recording or playing back input.
+	StopReplay,
+	/// Shows or hides the profiler overlay.
+	Profiler,
+	/// Writes recent profiled frames to a Chrome trace file.
+	ExportTrace(String),
 }
 
 enum MsgType {
@@ -45,35 +57,130 @@ struct Msg {
 	time: f32,
 }
 
+fn format_bytes(bytes: usize) -> String {
+	const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+	let mut size = bytes as f64;
+	let mut unit = 0;
+	while size >= 1024. && unit < UNITS.len() - 1 {
+		size /= 1024.;
+		unit += 1;
+	}
+	if unit == 0 {
+		format!("{} {}", bytes, UNITS[0])
+	} else {
+		format!("{:.1} {}", size, UNITS[unit])
+	}
+}
+
+fn resource_listing(resources: &[crate::ResourceInfo]) -> String {
+	let total = resources.iter().map(|info| info.memory_size).sum::<usize>();
+	let mut listing = format!("{} resources, {} total", resources.len(), format_bytes(total));
+	for info in resources {
+		listing += &format!(
+			"\n{} \"{}\" {:?}, {} refs, {}",
+			short_type_name(info.type_name), info.path, info.load_state, info.strong_count, format_bytes(info.memory_size),
+		);
+	}
+	listing
+}
+
+/// Log shown on screen, accessed through [`EngineCtx::console`](crate::EngineCtx::console).
 pub struct Console {
-	text_input: String,
 	messages: BufferDequeCell<Msg>,
+}
+
+impl Console {
+	fn msg(&self, msg: impl Into<String>, ty: MsgType) {
+		self.messages.push(Msg {
+			ty, content: msg.into(), time: 0.,
+		});
+	}
+
+	pub fn log(&self, msg: impl Into<String>) {
+		self.msg(msg, MsgType::Log);
+	}
+
+	pub fn warn(&self, msg: impl Into<String>) {
+		self.msg(msg, MsgType::Warning);
+	}
+
+	pub fn error(&self, msg: impl Into<String>) {
+		self.msg(msg, MsgType::Error);
+	}
+
+	pub(crate) fn new() -> Self {
+		Self { messages: BufferDequeCell::new() }
+	}
+}
+
+/// Plugin that runs commands typed into the console and draws its messages over the game.
+pub(crate) struct ConsolePlugin {
+	text_input: String,
+	messages: Vec<Msg>,
+	show_profiler: bool,
 	shader: Shader<Vertex, (), FontMaterial, Mat4>,
 	font_map: HashMap<Option<char>, Rect2D, FxBuildHasher>,
 	font_texture: GPUTexture2D,
 	font_sampler: Sampler,
 }
 
-impl Console {
-	pub(crate) fn update(&mut self, gfx: &GfxCtx, input: &super::input::InputCtx) {
-		let window_size = gfx.window_canvas.size.as_vec2();
-		gfx.set_canvas(&gfx.window_canvas, None);
+impl crate::Plugin for ConsolePlugin {
+	fn post_update(&mut self, c: &crate::EngineCtx) {
+		crate::profile_scope!("console");
 		// update text input
+		let input = &c.input;
 		for _ in 0..input.text_input.n_backspaces {
 			self.text_input.pop();
 		}
 		self.text_input.push_str(&input.text_input.text);
 		if input.text_input.enter {
-			match ron::de::from_str::<Cmd>(&self.text_input) {
-				Ok(cmd) => match cmd {
-					Cmd::Msg(s) => self.log(s),
-				},
-				Err(e) => {
-					self.error(format!("invalid command: {}", e));
-				},
-			};
+			self.run_cmd(c);
 			self.text_input.clear();
 		}
+		self.messages.extend(c.console.messages.take());
+		self.render(&c.gfx);
+	}
+}
+
+impl ConsolePlugin {
+	fn run_cmd(&mut self, c: &crate::EngineCtx) {
+		let console = &c.console;
+		match ron::de::from_str::<Cmd>(&self.text_input) {
+			Ok(cmd) => match cmd {
+				Cmd::Msg(s) => console.log(s),
+				Cmd::Resources => console.log(resource_listing(&c.resources())),
+				Cmd::Record(path) => match c.replay.borrow_mut().start_recording(&path) {
+					Ok(()) => console.log(format!("recording input to \"{}\"", path)),
+					Err(e) => console.error(format!("failed to record to \"{}\": {}", path, e)),
+				},
+				Cmd::Replay(path) => match c.replay.borrow_mut().start_playback(&path) {
+					Ok(()) => console.log(format!("replaying input from \"{}\"", path)),
+					Err(e) => console.error(format!("failed to replay \"{}\": {}", path, e)),
+				},
+				Cmd::StopReplay => {
+					let mut replay = c.replay.borrow_mut();
+					if replay.is_recording() {
+						console.log("stopped recording input");
+					} else if replay.is_playing() {
+						console.log("stopped replaying input");
+					}
+					replay.stop();
+				},
+				Cmd::Profiler => self.show_profiler = !self.show_profiler,
+				Cmd::ExportTrace(path) => match crate::profile::export_chrome_trace(&path) {
+					Ok(()) => console.log(format!("exported trace to \"{}\"", path)),
+					Err(e) => console.error(format!("failed to export trace to \"{}\": {}", path, e)),
+				},
+			},
+			Err(e) => {
+				console.error(format!("invalid command: {}", e));
+			},
+		};
+	}
+
+	fn render(&self, gfx: &GfxCtx) {
+		let window_size = gfx.window_canvas.size.as_vec2();
+		gfx.set_canvas(&gfx.window_canvas, None);
 
 		// render
 		let mat = Mat4::from_translation(Vec3::new(-1., 1., 0.)) * Mat4::from_scale(Vec3::new(2./window_size.x, -2./window_size.y, 1.));
@@ -92,7 +199,7 @@ impl Console {
 		cursor += padding;
 		for char in self.text_input.chars() {
 			if char != '\n' {
-				let rect = self.font_map[&Some(char)];
+				let rect = self.glyph(char);
 				mesh.uv_rect(Rect2D::with_extent(cursor + shadow_offset, char_size), rect, 0.5, black);
 				mesh.uv_rect(Rect2D::with_extent(cursor, char_size), rect, 0.5, Vertex::color(vec4(0.9, 0.9, 0.9, 1.0)));
 				cursor.x += char_size.x;
@@ -102,7 +209,7 @@ impl Console {
 		cursor.x = 0.;
 
 		// messages
-		for msg in self.messages.get_mut().iter().rev() {
+		for msg in self.messages.iter().rev() {
 			let (bg_color, fg_color) = match msg.ty {
 				MsgType::Log => (Vertex::color(vec4(0.1, 0.1, 0.3, 0.5)), Vertex::color(vec4(0.6, 0.6, 0.8, 1.0))),
 				MsgType::Warning => (Vertex::color(vec4(0.2, 0.2, 0.0, 0.5)), Vertex::color(vec4(0.8, 0.8, 0.5, 1.0))),
@@ -114,14 +221,14 @@ impl Console {
 			for line in msg.content.split('\n') {
 				bcursor.x = padding;
 				for word in line.split(' ') {
-					if bcursor.x + word.len() as f32 * char_size.x > window_size.x - padding {
+					if bcursor.x + word.chars().count() as f32 * char_size.x > window_size.x - padding {
 						bcursor.y += char_size.y;
 						bcursor.x = padding;
 						bcursor_max = window_size.x;
 					} else {
 						bcursor.x += char_size.x;
 					}
-					bcursor.x += word.len() as f32 * char_size.x;
+					bcursor.x += word.chars().count() as f32 * char_size.x;
 					bcursor_max = bcursor.x.max(bcursor_max);
 				}
 				bcursor.y += char_size.y;
@@ -133,14 +240,14 @@ impl Console {
 			for line in msg.content.split('\n') {
 				cursor.x = padding - char_size.x;
 				for word in line.split(' ') {
-					if cursor.x + word.len() as f32 * char_size.x > window_size.x - padding {
+					if cursor.x + word.chars().count() as f32 * char_size.x > window_size.x - padding {
 						cursor.y += char_size.y;
 						cursor.x = padding;
 					} else {
 						cursor.x += char_size.x;
 					}
 					for char in word.chars() {
-						let rect = self.font_map[&Some(char)];
+						let rect = self.glyph(char);
 						mesh.uv_rect(Rect2D::with_extent(cursor + shadow_offset, char_size), rect, 0.5, black);
 						mesh.uv_rect(Rect2D::with_extent(cursor, char_size), rect, 0.5, fg_color);
 						cursor.x += char_size.x;
@@ -154,6 +261,13 @@ impl Console {
 				break;
 			}
 		}
+
+		// profiler
+		if self.show_profiler {
+			if let Some(frame) = crate::profile::last_frame() {
+				self.profiler_overlay(&mut mesh, window_size, &frame);
+			}
+		}
 		let mesh = gfx.imm_mesh(Mesh::U16(mesh));
 
 		let material = gfx.material_cfg(FontMaterialRefs {
@@ -163,22 +277,49 @@ impl Console {
 		gfx.shader_cfg(&self.shader, &material).draw(&mesh, &GPUInstances::one(), mat);
 	}
 
-	fn msg(&self, msg: impl Into<String>, ty: MsgType) {
-		self.messages.push(Msg {
-			ty, content: msg.into(), time: 0.,
-		});
+	/// Returns the area of the font texture for a character, using `?` for characters that aren't in the font.
+	fn glyph(&self, char: char) -> Rect2D {
+		self.font_map.get(&Some(char)).copied().unwrap_or_else(|| self.font_map[&Some('?')])
 	}
 
-	pub fn log(&self, msg: impl Into<String>) {
-		self.msg(msg, MsgType::Log);
+	/// Draws text on a single line, stopping at `max_x`.
+	fn text(&self, mesh: &mut MeshU16<Vertex>, mut cursor: Vec2, char_size: Vec2, max_x: f32, text: &str, color: Vertex) {
+		for char in text.chars() {
+			if cursor.x + char_size.x > max_x {
+				break;
+			}
+			mesh.uv_rect(Rect2D::with_extent(cursor, char_size), self.glyph(char), 0.25, color);
+			cursor.x += char_size.x;
+		}
 	}
 
-	pub fn warn(&self, msg: impl Into<String>) {
-		self.msg(msg, MsgType::Warning);
-	}
+	/// Draws the scopes of a frame as bars along the bottom of the screen, with nested scopes below their parents.
+	fn profiler_overlay(&self, mesh: &mut MeshU16<Vertex>, window_size: Vec2, frame: &crate::profile::ProfileFrame) {
+		let char_size = Vec2::new(7.*2., 8.*2.);
+		let padding = 4.*2.;
+		let row_height = char_size.y + padding;
+		let rows = frame.spans.iter().map(|span| span.depth + 1).max().unwrap_or(0) as f32 + 1.;
+		let start = vec2(padding, window_size.y - rows * row_height - padding);
+		let width = window_size.x - padding * 2.;
+		let white = Vertex::color(vec4(0.9, 0.9, 0.9, 1.0));
 
-	pub fn error(&self, msg: impl Into<String>) {
-		self.msg(msg, MsgType::Error);
+		mesh.uv_rect(
+			Rect2D::new(start - padding, window_size),
+			self.font_map[&None], 0.75, Vertex::color(vec4(0., 0., 0.1, 0.75))
+		);
+		let header = format!("frame {:.2} ms ({:.0} fps)", frame.duration * 1000., 1. / frame.duration);
+		self.text(mesh, start, char_size, window_size.x, &header, white);
+		for (i, span) in frame.spans.iter().enumerate() {
+			let x = start.x + (span.start / frame.duration) as f32 * width;
+			let w = ((span.duration / frame.duration) as f32 * width).max(1.);
+			let rect = Rect2D::with_extent(vec2(x, start.y + (span.depth + 1) as f32 * row_height), vec2(w, row_height - 2.));
+			// neighbouring bars alternate in brightness so they can be told apart
+			let shade = if i % 2 == 0 { 0.5 } else { 0.35 };
+			let depth_tint = span.depth as f32 * 0.1;
+			mesh.uv_rect(rect, self.font_map[&None], 0.5, Vertex::color(vec4(shade, shade * 0.6 + depth_tint, 0.2, 0.9)));
+			let label = format!("{} {:.2} ms", span.name, span.duration * 1000.);
+			self.text(mesh, rect.start + padding / 2., char_size, rect.end.x, &label, white);
+		}
 	}
 
 	pub(crate) fn new(gfx: &GfxCtx) -> Self {
@@ -203,7 +344,8 @@ impl Console {
 
 		Self {
 			text_input: String::new(),
-			messages: BufferDequeCell::new(),
+			messages: vec![],
+			show_profiler: false,
 			font_texture: gfx.register_texture2d(font_atlas),
 			font_map,
 			font_sampler: gfx.register_sampler(SamplerDefinition::default()),
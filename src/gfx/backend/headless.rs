@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use super::*;
+use crate::gfx::{draw::DrawCmd, mesh::{InstancesDraw, MeshDraw}, resource::{ResourceFreeType, ResourceUpdate}, CanvasID};
+
+/// Kind of GPU resource in a [`HeadlessResourceUpdate`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HeadlessResourceKind {
+	Shader,
+	Texture2D,
+	Mesh,
+	Instances,
+	Uniform,
+	Framebuffer,
+	Sampler,
+}
+
+/// Resource update recorded by the headless backend.
+///
+/// Ids are only unique between resources of the same kind.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HeadlessResourceUpdate {
+	Create { kind: HeadlessResourceKind, id: usize },
+	Free { kind: HeadlessResourceKind, id: usize },
+}
+
+/// Draw command recorded by the headless backend.
+#[derive(Clone, PartialEq, Debug)]
+pub enum HeadlessDrawCmd {
+	/// Sets the target of the following draws, where `framebuffer` is `None` for the [window canvas](GfxCtx::window_canvas).
+	SetCanvas { framebuffer: Option<usize>, clear_color: Option<glam::Vec4> },
+	SetShader { shader: usize },
+	SetMaterial { slot: usize },
+	/// Draws `instances` instances of a mesh, where `elements` is the number of indices (or vertices for meshes without indices).
+	DrawMesh { elements: u32, instances: u32 },
+}
+
+/// Everything a frame sent to the GPU, recorded when running with [`run_headless`](crate::run_headless) so tests can check what was rendered.
+#[derive(Clone, Default, Debug)]
+pub struct HeadlessFrame {
+	pub resource_updates: Vec<HeadlessResourceUpdate>,
+	pub draw_cmds: Vec<HeadlessDrawCmd>,
+}
+
+/// Backend used when running without a window, which records what each frame queued by [`GfxCtx`] instead of rendering it.
+#[derive(Default)]
+pub struct HeadlessGfxBackend {
+	/// Number of elements in each registered mesh, so draws of them can be recorded with a size.
+	mesh_elements: HashMap<usize, u32>,
+	instance_counts: HashMap<usize, u32>,
+}
+
+impl HeadlessGfxBackend {
+	fn record_update(&mut self, update: &ResourceUpdate) -> HeadlessResourceUpdate {
+		let (kind, id) = match update {
+			ResourceUpdate::CreateShader { id, .. } => (HeadlessResourceKind::Shader, *id),
+			ResourceUpdate::CreateTexture2D { id, .. } => (HeadlessResourceKind::Texture2D, *id),
+			ResourceUpdate::CreateMesh { id, data } => {
+				let elements = data.indices.as_ref().map_or(data.n_vertices, |indices| indices.n);
+				self.mesh_elements.insert(*id, elements as u32);
+				(HeadlessResourceKind::Mesh, *id)
+			},
+			ResourceUpdate::CreateInstances { id, data } => {
+				self.instance_counts.insert(*id, data.n as u32);
+				(HeadlessResourceKind::Instances, *id)
+			},
+			ResourceUpdate::CreateUniform { id, .. } => (HeadlessResourceKind::Uniform, *id),
+			ResourceUpdate::CreateFramebuffer { id, .. } => (HeadlessResourceKind::Framebuffer, *id),
+			ResourceUpdate::CreateSampler { id, .. } => (HeadlessResourceKind::Sampler, *id),
+			ResourceUpdate::Free { id, ty } => {
+				let kind = match ty {
+					ResourceFreeType::Shader => HeadlessResourceKind::Shader,
+					ResourceFreeType::Texture2D => HeadlessResourceKind::Texture2D,
+					ResourceFreeType::Mesh => {
+						self.mesh_elements.remove(id);
+						HeadlessResourceKind::Mesh
+					},
+					ResourceFreeType::Instances => {
+						self.instance_counts.remove(id);
+						HeadlessResourceKind::Instances
+					},
+					ResourceFreeType::Uniform => HeadlessResourceKind::Uniform,
+					ResourceFreeType::Framebuffer => HeadlessResourceKind::Framebuffer,
+					ResourceFreeType::Sampler => HeadlessResourceKind::Sampler,
+				};
+				return HeadlessResourceUpdate::Free { kind, id: *id };
+			},
+		};
+		HeadlessResourceUpdate::Create { kind, id }
+	}
+
+	fn record_draw(&self, cmd: &DrawCmd) -> HeadlessDrawCmd {
+		match cmd {
+			DrawCmd::SetCanvas { id, clear_color } => HeadlessDrawCmd::SetCanvas {
+				framebuffer: match id {
+					CanvasID::Framebuffer(id) => Some(*id),
+					CanvasID::Screen => None,
+				},
+				clear_color: *clear_color,
+			},
+			DrawCmd::SetShader { id } => HeadlessDrawCmd::SetShader { shader: *id },
+			DrawCmd::SetMaterial { slot, .. } => HeadlessDrawCmd::SetMaterial { slot: *slot },
+			DrawCmd::DrawMesh(desc) => HeadlessDrawCmd::DrawMesh {
+				elements: match &desc.mesh {
+					MeshDraw::Range(range) => range.len() as u32,
+					MeshDraw::Immediate(draw) => draw.indices.as_ref().map_or(draw.n, |indices| indices.n),
+					MeshDraw::Resource(id) => self.mesh_elements.get(id).copied().unwrap_or(0),
+				},
+				instances: match &desc.instances {
+					InstancesDraw::Range(range) => range.len() as u32,
+					InstancesDraw::Immediate(draw) => draw.n,
+					InstancesDraw::Resource(id) => self.instance_counts.get(id).copied().unwrap_or(0),
+				},
+			},
+		}
+	}
+}
+
+impl GfxBackend for HeadlessGfxBackend {
+	fn render(&mut self, c: &mut GfxCtx) {
+		let resource_updates = c.frame_data.resource_update_queue.get_mut().drain(..).map(|update| self.record_update(&update)).collect();
+		let draw_cmds = c.frame_data.draw_cmd_queue.get_mut().iter().map(|cmd| self.record_draw(cmd)).collect();
+		c.headless_frame = Some(HeadlessFrame { resource_updates, draw_cmds });
+	}
+}
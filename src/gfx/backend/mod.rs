@@ -1,10 +1,14 @@
 
 mod vulkan;
+mod headless;
 
 pub use super::GfxCtx;
 
 pub use vulkan::VulkanGfxBackend;
+pub use headless::{HeadlessDrawCmd, HeadlessFrame, HeadlessGfxBackend, HeadlessResourceKind, HeadlessResourceUpdate};
 
 pub trait GfxBackend {
 	fn render(&mut self, c: &mut GfxCtx);
+	/// Called when the window has changed in a way that requires the swapchain to be rebuilt.
+	fn invalidate_swapchain(&mut self) {}
 }
\ No newline at end of file
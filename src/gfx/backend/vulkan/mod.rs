@@ -44,7 +44,9 @@ pub struct VulkanGfxBackend {
 }
 
 impl VulkanGfxBackend {
-	pub fn load(sdl_video: &sdl2::VideoSubsystem, mut window_builder: sdl2::video::WindowBuilder) -> anyhow::Result<(Self, sdl2::video::Window)> {
+	pub fn load(
+		sdl_video: &sdl2::VideoSubsystem, mut window_builder: sdl2::video::WindowBuilder, present_mode: crate::PresentMode,
+	) -> anyhow::Result<(Self, sdl2::video::Window)> {
 
 		// do sdl setup
 		sdl_video.vulkan_load_library_default().unwrap();
@@ -56,7 +58,7 @@ impl VulkanGfxBackend {
 		).collect::<Vec<_>>())?);
 
 		// create surface
-		let surface = VKSurface::new(&ctx, &window)?;
+		let surface = VKSurface::new(&ctx, &window, present_mode)?;
 
 		// create per-frame resources
 		let frame_resources = (
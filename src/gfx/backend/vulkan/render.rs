@@ -45,6 +45,10 @@ fn staging_buffer_buffer_copy_and_align(
 }
 
 impl GfxBackend for VulkanGfxBackend {
+	fn invalidate_swapchain(&mut self) {
+		self.surface.swapchain_is_bad = true;
+	}
+
 	fn render(&mut self, c: &mut GfxCtx) {
 		//////////////////////////////////////////////////////////////////////////////////////////
 		// STEP 0:
@@ -61,8 +65,10 @@ impl GfxBackend for VulkanGfxBackend {
 		};
 
 		// wait for resources to be usable (then reset fence)
-		unsafe{device.wait_for_fences(&[cfr.resources_usable], true, u64::MAX)}.unwrap();
-		//c.backend_debug_info = format!("gpu wait time: {} micros", gpu_wait_start.elapsed().subsec_micros());
+		{
+			crate::profile_scope!("gpu wait");
+			unsafe{device.wait_for_fences(&[cfr.resources_usable], true, u64::MAX)}.unwrap();
+		}
 		unsafe{device.reset_fences(&[cfr.resources_usable])}.unwrap();
 
 		// destroy resources that are queued for destruction
@@ -93,6 +99,7 @@ impl GfxBackend for VulkanGfxBackend {
 		// handle resource updates
 		//////////////////////////////////////////////////////////////////////////////////////////
 		
+		let resource_updates_scope = crate::profile::ProfileScope::new("resource updates");
 		for resource_update in c.frame_data.resource_update_queue.get_mut().drain(..) {
 			use crate::gfx::resource::ResourceUpdate;
 			match resource_update {
@@ -202,6 +209,8 @@ impl GfxBackend for VulkanGfxBackend {
 			}
 		}
 
+		drop(resource_updates_scope);
+
 		//////////////////////////////////////////////////////////////////////////////////////////
 		// STEP 2:
 		// prepare for swapchain rendering (if able)
@@ -9,6 +9,21 @@ fn inside(window: UVec2, min: vk::Extent2D, max: vk::Extent2D) -> vk::Extent2D {
 	}
 }
 
+/// Picks the closest supported present mode, falling back to FIFO which is always supported.
+fn choose_present_mode(desired: crate::PresentMode, supported: &[vk::PresentModeKHR]) -> vk::PresentModeKHR {
+	let desired = match desired {
+		crate::PresentMode::VSync => vk::PresentModeKHR::FIFO,
+		crate::PresentMode::AdaptiveVSync => vk::PresentModeKHR::FIFO_RELAXED,
+		crate::PresentMode::Mailbox => vk::PresentModeKHR::MAILBOX,
+		crate::PresentMode::Immediate => vk::PresentModeKHR::IMMEDIATE,
+	};
+	if supported.contains(&desired) {
+		desired
+	} else {
+		vk::PresentModeKHR::FIFO
+	}
+}
+
 pub struct VKSwapchain {
 	ctx: Arc<VKCtx>,
 	pub color_images: Vec<VKImage>,
@@ -23,6 +38,7 @@ impl VKSwapchain {
 		surface: &vk::SurfaceKHR,
 		surface_format: vk::SurfaceFormatKHR,
 		surface_capabilities: &vk::SurfaceCapabilitiesKHR,
+		present_mode: vk::PresentModeKHR,
 		window_size: UVec2,
 	) -> Self {
 
@@ -41,7 +57,7 @@ impl VKSwapchain {
 			image_color_space: surface_format.color_space,
 			image_extent: extent,
 			image_array_layers: 1,
-			present_mode: vk::PresentModeKHR::FIFO,
+			present_mode,
 			image_usage: vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_DST,
 			pre_transform: vk::SurfaceTransformFlagsKHR::IDENTITY,
 			composite_alpha: vk::CompositeAlphaFlagsKHR::INHERIT,
@@ -91,12 +107,13 @@ pub struct VKSurface {
 	inner: VKSurfaceInner,
 	pub format: vk::SurfaceFormatKHR,
 	pub capabilities: vk::SurfaceCapabilitiesKHR,
+	pub present_mode: vk::PresentModeKHR,
 	pub swapchain_is_bad: bool,
 }
 
 impl VKSurface {
-	pub fn new(ctx: &Arc<VKCtx>,window: &sdl2::video::Window) -> anyhow::Result<Self> {
-		let window_size = window.size();
+	pub fn new(ctx: &Arc<VKCtx>, window: &sdl2::video::Window, present_mode: crate::PresentMode) -> anyhow::Result<Self> {
+		let window_size = window.vulkan_drawable_size();
 
 		// create surface
 		let surface = vk::SurfaceKHR::from_raw(window.vulkan_create_surface(ctx.inst.handle().as_raw() as usize).unwrap());
@@ -105,14 +122,16 @@ impl VKSurface {
 			let surface_formats = unsafe{ctx.surface_inst.get_physical_device_surface_formats(ctx.physical_device, surface)}?;
 			let format = surface_formats[0];
 			let capabilities = unsafe{ctx.surface_inst.get_physical_device_surface_capabilities(ctx.physical_device, surface)}?;
+			let present_modes = unsafe{ctx.surface_inst.get_physical_device_surface_present_modes(ctx.physical_device, surface)}?;
+			let present_mode = choose_present_mode(present_mode, &present_modes);
 
 			// swapchain
-			let swapchain = VKSwapchain::new(ctx, &surface, format, &capabilities, window_size.into());
+			let swapchain = VKSwapchain::new(ctx, &surface, format, &capabilities, present_mode, window_size.into());
 
 			let inner = VKSurfaceInner { surface, ctx: ctx.clone() };
 
 			Ok(Self {
-				inner, capabilities, format, swapchain, swapchain_is_bad: false,
+				inner, capabilities, format, present_mode, swapchain, swapchain_is_bad: false,
 			})
 		}.map_err(|e| {
 			unsafe { ctx.surface_inst.destroy_surface(surface, None) };
@@ -127,7 +146,7 @@ impl VKSurface {
 
 		// recreate
 		self.swapchain = VKSwapchain::new(
-			&self.inner.ctx, &self.inner.surface, self.format, &self.capabilities, window_size
+			&self.inner.ctx, &self.inner.surface, self.format, &self.capabilities, self.present_mode, window_size
 		);
 	}
 }
\ No newline at end of file
@@ -63,6 +63,29 @@ impl GPUTexture2D {
 	}
 }
 
+/// Loaded from PNG files in SRGB format.
+impl crate::Resource for GPUTexture2D {
+	type Decoded = Texture<U8Vec4>;
+
+	fn decode(data: Vec<u8>) -> anyhow::Result<Texture<U8Vec4>> {
+		Texture::from_png(&data).ok_or_else(|| anyhow::anyhow!("invalid png"))
+	}
+
+	fn load(decoded: Texture<U8Vec4>, ctx: &crate::EngineCtx) -> anyhow::Result<Self> {
+		Ok(ctx.gfx.register_texture2d_srgb(decoded))
+	}
+
+	/// A magenta and black checkerboard.
+	fn default(ctx: &crate::EngineCtx) -> Self {
+		ctx.gfx.register_texture2d_srgb(Texture::missing())
+	}
+
+	/// Size of the texture in GPU memory.
+	fn memory_size(&self) -> usize {
+		self.rc.size.x as usize * self.rc.size.y as usize * std::mem::size_of::<U8Vec4>()
+	}
+}
+
 impl MaterialAttribute for GPUTexture2D {
 	fn id() -> MaterialAttributeID {
 		MaterialAttributeID { inner: MaterialAttributeIDInner::Texture2D }
@@ -11,6 +11,7 @@ mod resource;
 mod draw;
 mod material;
 mod texture;
+mod window;
 
 use crate::util::*;
 
@@ -25,6 +26,9 @@ pub use uniform::*;
 pub use attribute::*;
 pub use mesh::*;
 pub use texture::*;
+pub use window::{DisplayMode, MonitorInfo};
+pub(crate) use window::WindowCmd;
+pub use backend::{HeadlessDrawCmd, HeadlessFrame, HeadlessResourceKind, HeadlessResourceUpdate};
 use resource::*;
 
 #[derive(Default)]
@@ -73,6 +77,11 @@ struct GfxResources {
 pub struct GfxCtx {
 	pub window_canvas: Canvas,
 	pub(crate) frame_data: GfxFrameData,
+	pub(crate) window_cmds: BufferCell<WindowCmd>,
+	pub(crate) monitors: Vec<MonitorInfo>,
+	pub(crate) window_monitor: Option<usize>,
+	/// Last frame recorded by the headless backend.
+	pub(crate) headless_frame: Option<HeadlessFrame>,
 	resources: GfxResources,
 }
 
@@ -84,6 +93,10 @@ impl GfxCtx {
 			window_canvas: Canvas { id: CanvasID::Screen, size: UVec2::ONE },
 			resources: GfxResources::default(),
 			frame_data: GfxFrameData::default(),
+			window_cmds: BufferCell::new(),
+			monitors: Vec::new(),
+			window_monitor: None,
+			headless_frame: None,
 		}
 	}
 
@@ -254,13 +267,18 @@ impl GfxCtx {
 }
 
 pub(crate) struct GfxSys {
-	window: sdl2::video::Window,
+	/// `None` when running headless.
+	window: Option<sdl2::video::Window>,
+	headless_size: UVec2,
 	backend: Box<dyn backend::GfxBackend>,
 }
 
 impl GfxSys {
 	pub fn start_update(&mut self, c: &mut GfxCtx, reset_frame_data: bool) {
-		c.window_canvas.size = self.window.size().into();
+		self.apply_window_cmds(c);
+		c.window_canvas.size = self.window.as_ref().map_or(self.headless_size, |window| window.vulkan_drawable_size().into());
+		let display_index = self.window.as_ref().and_then(|window| window.display_index().ok());
+		c.window_monitor = display_index.and_then(|i| c.monitors.iter().position(|monitor| monitor.display_index == i));
 		if reset_frame_data {
 			c.frame_data.reset();
 		}
@@ -292,13 +310,42 @@ impl GfxSys {
 		self.backend.render(c);
 	}
 
-	pub fn new(sdl_video: &sdl2::VideoSubsystem) -> Self {
-		let mut window_builder = sdl2::video::WindowBuilder::new(&sdl_video, "", 800, 800);
+	/// Creates the window and its renderer, warning on the console about any size limits in the config that can't be applied.
+	pub fn new(sdl_video: &sdl2::VideoSubsystem, config: &crate::RunConfig, console: &crate::console::Console) -> Self {
+		let mut window_builder = sdl2::video::WindowBuilder::new(sdl_video, &config.title, config.size.0, config.size.1);
 		window_builder.position_centered();
-		window_builder.resizable();
-		let (backend, window) = backend::VulkanGfxBackend::load(sdl_video, window_builder).unwrap();
+		if config.resizable {
+			window_builder.resizable();
+		}
+		if config.high_dpi {
+			window_builder.allow_highdpi();
+		}
+		match config.window_mode {
+			crate::WindowMode::Windowed => {},
+			crate::WindowMode::Borderless => { window_builder.borderless(); },
+			crate::WindowMode::Fullscreen => { window_builder.fullscreen(); },
+			crate::WindowMode::FullscreenDesktop => { window_builder.fullscreen_desktop(); },
+		}
+		let (backend, mut window) = backend::VulkanGfxBackend::load(sdl_video, window_builder, config.present_mode).unwrap();
+		if let Some((w, h)) = config.min_size {
+			if let Err(e) = window.set_minimum_size(w, h) {
+				console.warn(format!("ignoring minimum window size {}x{}: {}", w, h, e));
+			}
+		}
+		if let Some((w, h)) = config.max_size {
+			if let Err(e) = window.set_maximum_size(w, h) {
+				console.warn(format!("ignoring maximum window size {}x{}: {}", w, h, e));
+			}
+		}
+		Self {
+			backend: Box::new(backend), window: Some(window), headless_size: UVec2::ZERO,
+		}
+	}
+
+	/// Creates a graphics system without a window or GPU that records what is drawn instead of rendering it.
+	pub fn new_headless(size: UVec2) -> Self {
 		Self {
-			backend: Box::new(backend), window
+			backend: Box::new(backend::HeadlessGfxBackend::default()), window: None, headless_size: size,
 		}
 	}
 }
\ No newline at end of file
@@ -25,6 +25,60 @@ pub struct ShaderDefinition {
 	pub color_blend: Option<ColorBlend>,
 }
 
+/// Shader code loaded as a resource.
+/// 
+/// Use [`definition`](ShaderSource::definition) to create a [`ShaderDefinition`] for [`register_shader`](GfxCtx::register_shader).
+#[derive(Clone, Debug)]
+pub struct ShaderSource {
+	pub code: String,
+}
+
+impl ShaderSource {
+	/// Creates a [`ShaderDefinition`] with this code and default settings.
+	pub fn definition(&self) -> ShaderDefinition {
+		ShaderDefinition { code: self.code.clone(), ..Default::default() }
+	}
+}
+
+impl crate::Resource for ShaderSource {
+	type Decoded = Self;
+
+	fn decode(data: Vec<u8>) -> anyhow::Result<Self> {
+		let code = String::from_utf8(data)?;
+		for section in ["[varying]", "[vertex]", "[fragment]"] {
+			if !code.contains(section) {
+				anyhow::bail!("missing {} section", section);
+			}
+		}
+		Ok(Self { code })
+	}
+
+	fn load(decoded: Self, _ctx: &crate::EngineCtx) -> anyhow::Result<Self> {
+		Ok(decoded)
+	}
+
+	/// A shader that compiles with any layout but does not draw anything.
+	fn default(_ctx: &crate::EngineCtx) -> Self {
+		Self { code: r#"
+			[varying]
+
+			[vertex]
+			void main() {
+				gl_Position = vec4(0.0);
+			}
+
+			[fragment]
+			void main() {
+				out_color = vec4(1.0, 0.0, 1.0, 1.0);
+			}
+		"#.into() }
+	}
+
+	fn memory_size(&self) -> usize {
+		std::mem::size_of::<Self>() + self.code.capacity()
+	}
+}
+
 #[derive(Clone)]
 pub(crate) struct ShaderFullDefinition {
 	pub vertex_layout: StructLayout<VertexAttributeID>,
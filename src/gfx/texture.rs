@@ -120,6 +120,44 @@ impl Texture<U8Vec4> {
 		let frame_info = reader.next_frame(&mut bytes).ok()?;
 		Self::new_from_bytes(bytes, UVec3::new(frame_info.width, frame_info.height, 1))
 	}
+
+	/// Creates a magenta and black checkerboard used to make missing textures obvious.
+	pub(crate) fn missing() -> Self {
+		const SIZE: u32 = 64;
+		const SQUARE: u32 = 8;
+		let mut texture = Self::new_empty(UVec3::new(SIZE, SIZE, 1));
+		for y in 0..SIZE {
+			for x in 0..SIZE {
+				texture.pixels[(y * SIZE + x) as usize] = if (x / SQUARE + y / SQUARE).is_multiple_of(2) {
+					U8Vec4::new(255, 0, 255, 255)
+				} else {
+					U8Vec4::new(0, 0, 0, 255)
+				};
+			}
+		}
+		texture
+	}
+}
+
+impl crate::Resource for Texture<U8Vec4> {
+	type Decoded = Self;
+
+	fn decode(data: Vec<u8>) -> anyhow::Result<Self> {
+		Self::from_png(&data).ok_or_else(|| anyhow::anyhow!("invalid png"))
+	}
+
+	fn load(decoded: Self, _ctx: &crate::EngineCtx) -> anyhow::Result<Self> {
+		Ok(decoded)
+	}
+
+	/// A magenta and black checkerboard.
+	fn default(_ctx: &crate::EngineCtx) -> Self {
+		Self::missing()
+	}
+
+	fn memory_size(&self) -> usize {
+		std::mem::size_of::<Self>() + self.pixels.capacity() * std::mem::size_of::<U8Vec4>()
+	}
 }
 
 pub fn pack_sprite_atlas_array<'image, Key: std::hash::Hash + PartialEq + Eq, T: TextureFormat>(
@@ -0,0 +1,145 @@
+use glam::{IVec2, U8Vec4, UVec2};
+
+use super::*;
+
+/// A resolution and refresh rate supported by a monitor.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DisplayMode {
+	pub size: UVec2,
+	/// Refresh rate in hertz, or 0 if it is unknown.
+	pub refresh_rate: i32,
+}
+
+impl From<sdl2::video::DisplayMode> for DisplayMode {
+	fn from(mode: sdl2::video::DisplayMode) -> Self {
+		Self { size: UVec2::new(mode.w.max(0) as u32, mode.h.max(0) as u32), refresh_rate: mode.refresh_rate }
+	}
+}
+
+/// Information about a connected monitor.
+#[derive(Clone, Debug)]
+pub struct MonitorInfo {
+	pub name: String,
+	/// Position of the top left corner of the monitor on the desktop.
+	pub position: IVec2,
+	pub size: UVec2,
+	/// Display mode used by the desktop.
+	pub desktop_mode: DisplayMode,
+	/// Every display mode supported by the monitor, which can be used for [`Fullscreen`](crate::WindowMode::Fullscreen).
+	pub modes: Vec<DisplayMode>,
+	/// Index of the display in SDL, which can differ from the index in [`monitors`](crate::EngineCtx::monitors) if a display couldn't be queried.
+	pub(crate) display_index: i32,
+}
+
+/// Changes to the window requested through [`EngineCtx`](crate::EngineCtx), applied at the start of the next frame.
+pub(crate) enum WindowCmd {
+	Title(String),
+	Size(UVec2),
+	Mode(crate::WindowMode),
+	CursorVisible(bool),
+	CursorGrab(bool),
+	RelativeMouse(bool),
+	Icon(Texture<U8Vec4>),
+}
+
+fn query_monitor(video: &sdl2::VideoSubsystem, i: i32) -> Result<MonitorInfo, String> {
+	let bounds = video.display_bounds(i)?;
+	let modes = (0..video.num_display_modes(i)?).map(|j| video.display_mode(i, j).map(DisplayMode::from)).collect::<Result<Vec<_>, _>>()?;
+	Ok(MonitorInfo {
+		name: video.display_name(i)?,
+		position: IVec2::new(bounds.x(), bounds.y()),
+		size: UVec2::new(bounds.width(), bounds.height()),
+		desktop_mode: video.desktop_display_mode(i)?.into(),
+		modes,
+		display_index: i,
+	})
+}
+
+impl GfxSys {
+	/// Updates the cached list of monitors, which should be done whenever one is connected or disconnected.
+	pub fn refresh_monitors(&mut self, c: &mut GfxCtx) {
+		c.monitors.clear();
+		if let Some(window) = &self.window {
+			let video = window.subsystem();
+			for i in 0..video.num_video_displays().unwrap_or(0) {
+				// monitors can disappear while they are being queried
+				if let Ok(monitor) = query_monitor(video, i) {
+					c.monitors.push(monitor);
+				}
+			}
+		}
+	}
+
+	/// Returns the ratio between the size of the window canvas in pixels and the size of the window in screen coordinates.
+	pub fn canvas_scale(&self) -> glam::Vec2 {
+		let Some(window) = &self.window else {
+			return glam::Vec2::ONE;
+		};
+		let (w, h) = window.size();
+		let (canvas_w, canvas_h) = window.vulkan_drawable_size();
+		if w == 0 || h == 0 {
+			glam::Vec2::ONE
+		} else {
+			glam::vec2(canvas_w as f32 / w as f32, canvas_h as f32 / h as f32)
+		}
+	}
+
+	/// Whether [relative mouse mode](crate::EngineCtx::set_relative_mouse_mode) is enabled, which is always `false` without a window.
+	pub fn relative_mouse_mode(&self) -> bool {
+		self.window.as_ref().is_some_and(|window| window.subsystem().sdl().mouse().relative_mouse_mode())
+	}
+
+	pub(crate) fn apply_window_cmds(&mut self, c: &mut GfxCtx) {
+		let cmds = std::mem::take(c.window_cmds.get_mut());
+		let Some(window) = &mut self.window else {
+			for cmd in cmds {
+				if let WindowCmd::Size(size) = cmd {
+					self.headless_size = size;
+				}
+			}
+			return;
+		};
+		for cmd in cmds {
+			match cmd {
+				WindowCmd::Title(title) => {
+					// titles with nul bytes are ignored
+					let _ = window.set_title(&title);
+				},
+				WindowCmd::Size(size) => {
+					if window.set_size(size.x, size.y).is_ok() {
+						self.backend.invalidate_swapchain();
+					}
+				},
+				WindowCmd::Mode(mode) => {
+					use sdl2::video::FullscreenType;
+					let (fullscreen, bordered) = match mode {
+						crate::WindowMode::Windowed => (FullscreenType::Off, true),
+						crate::WindowMode::Borderless => (FullscreenType::Off, false),
+						crate::WindowMode::Fullscreen => (FullscreenType::True, true),
+						crate::WindowMode::FullscreenDesktop => (FullscreenType::Desktop, true),
+					};
+					let _ = window.set_fullscreen(fullscreen);
+					window.set_bordered(bordered);
+					self.backend.invalidate_swapchain();
+				},
+				WindowCmd::CursorVisible(visible) => {
+					window.subsystem().sdl().mouse().show_cursor(visible);
+				},
+				WindowCmd::CursorGrab(grab) => {
+					window.set_mouse_grab(grab);
+				},
+				WindowCmd::RelativeMouse(enabled) => {
+					window.subsystem().sdl().mouse().set_relative_mouse_mode(enabled);
+				},
+				WindowCmd::Icon(mut icon) => {
+					let size = icon.size_2d();
+					icon.pixels.truncate(icon.area() as usize);
+					let bytes = bytemuck::cast_slice_mut(&mut icon.pixels);
+					if let Ok(surface) = sdl2::surface::Surface::from_data(bytes, size.x, size.y, size.x * 4, sdl2::pixels::PixelFormatEnum::RGBA32) {
+						window.set_icon(surface);
+					}
+				},
+			}
+		}
+	}
+}
@@ -1,4 +1,4 @@
-use std::{cell::{OnceCell, RefCell}, collections::HashMap, io::Read, marker::PhantomData, ops::{Deref, Sub}, path::PathBuf, rc::{Rc, Weak}, u16, usize};
+use std::{cell::{OnceCell, RefCell}, collections::HashMap, io::Read, marker::PhantomData, ops::{Deref, Sub}, path::PathBuf, sync::{Arc, Weak}, u16, usize};
 
 mod json;
 use json::*;
@@ -56,7 +56,7 @@ pub struct Primitive {
 	pub colors: Vec<Vec<[f32;4]>>,
 	pub joints: Vec<Vec<[u16;4]>>,
 	pub weights: Vec<Vec<[f32;4]>>,
-	pub material: Option<Rc<Material>>,
+	pub material: Option<Arc<Material>>,
 }
 
 #[derive(Debug)]
@@ -72,7 +72,7 @@ pub struct Node {
 	pub name: Option<String>,
 	pub parent: Option<NodeID>,
 	pub children: Vec<NodeID>,
-	pub mesh: Option<Rc<Mesh>>,
+	pub mesh: Option<Arc<Mesh>>,
 	pub local_transform: glam::Mat4,
 	pub global_transform: glam::Mat4,
 }
@@ -101,9 +101,55 @@ impl NodeGraph {
 #[derive(Debug)]
 pub struct GltfDecoded {
 	pub nodes: NodeGraph,
-	pub materials: Vec<Rc<Material>>,
-	pub scene: Rc<Scene>,
-	pub scenes: Vec<Rc<Scene>>,
+	pub materials: Vec<Arc<Material>>,
+	pub scene: Arc<Scene>,
+	pub scenes: Vec<Arc<Scene>>,
+}
+
+impl crate::Resource for GltfDecoded {
+	type Decoded = Self;
+
+	fn decode(data: Vec<u8>) -> anyhow::Result<Self> {
+		Ok(decode_gltf_bytes(&data)?)
+	}
+
+	/// Reads external buffers relative to the `.gltf` file through the mounted filesystem.
+	fn decode_with(data: Vec<u8>, ctx: &crate::DecodeCtx) -> anyhow::Result<Self> {
+		Ok(decode_gltf_with(&data, |uri| Ok(ctx.read_relative(uri)?))?)
+	}
+
+	fn load(decoded: Self, _ctx: &crate::EngineCtx) -> anyhow::Result<Self> {
+		Ok(decoded)
+	}
+
+	/// An empty scene.
+	fn default(_ctx: &crate::EngineCtx) -> Self {
+		let scene = Arc::new(Scene { name: None, root_nodes: vec![] });
+		Self {
+			nodes: NodeGraph { nodes: vec![] },
+			materials: vec![],
+			scenes: vec![scene.clone()],
+			scene,
+		}
+	}
+
+	/// Size of the vertex and index data, which makes up almost all of a scene.
+	fn memory_size(&self) -> usize {
+		fn vec_size<T>(v: &Vec<T>) -> usize {
+			v.capacity() * std::mem::size_of::<T>()
+		}
+		let mut meshes = self.nodes.iter().filter_map(|node| node.mesh.as_ref()).collect::<Vec<_>>();
+		meshes.sort_by_key(|mesh| Arc::as_ptr(mesh));
+		meshes.dedup_by_key(|mesh| Arc::as_ptr(mesh));
+		let primitives_size = meshes.iter().flat_map(|mesh| &mesh.primitives).map(|p| {
+			p.indices.as_ref().map_or(0, vec_size) + vec_size(&p.positions) + vec_size(&p.normals) + vec_size(&p.tangents)
+				+ p.tex_coords.iter().map(vec_size).sum::<usize>()
+				+ p.colors.iter().map(vec_size).sum::<usize>()
+				+ p.joints.iter().map(vec_size).sum::<usize>()
+				+ p.weights.iter().map(vec_size).sum::<usize>()
+		}).sum::<usize>();
+		std::mem::size_of::<Self>() + self.nodes.nodes.len() * std::mem::size_of::<Node>() + primitives_size
+	}
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -126,6 +172,10 @@ pub enum GltfError {
 	MissingFeature(String),
 	#[error("unsupported version \"{0}\", only version 2 is supported")]
 	UnsupportedVersion(u32),
+	#[error("external buffer \"{0}\" cannot be loaded when decoding from bytes")]
+	ExternalBuffer(String),
+	#[error("failed to read external buffer")]
+	ExternalFile(#[from] crate::VfsError),
 }
 
 const COMPONENT_I8: u32 = 5120;
@@ -192,6 +242,29 @@ fn validate_index<T>(data: &[T], i: usize) -> Result<NodeID, GltfError> {
 pub fn decode_gltf(path: std::path::PathBuf) -> Result<GltfDecoded, GltfError> {
 	let path_parent = std::path::PathBuf::from(path.parent().ok_or(GltfError::InvalidPath)?);
 	let file = std::fs::read(path).map_err(|e| GltfError::IoError(e))?;
+	decode_gltf_with(&file, |uri| {
+		let path = std::path::PathBuf::try_from(uri).map_err(|_| GltfError::InvalidURI(uri.to_owned()))?;
+		if path.is_absolute() {
+			return Err(GltfError::AbsoluteURI(path));
+		}
+		if path.components().find(|c| *c == std::path::Component::ParentDir).is_some() {
+			return Err(GltfError::ParentDirURI(path));
+		}
+		let mut full_path = path_parent.clone();
+		full_path.push(path);
+		Ok(std::fs::read(full_path)?)
+	})
+}
+
+/// Decodes a glTF file that is already in memory.
+/// 
+/// Files that reference external buffers (such as most `.gltf` files) will fail with [`GltfError::ExternalBuffer`].
+/// Loading them as a resource with [`EngineCtx::load`](crate::EngineCtx::load) reads their buffers through the mounted filesystem instead.
+pub fn decode_gltf_bytes(file: &[u8]) -> Result<GltfDecoded, GltfError> {
+	decode_gltf_with(file, |uri| Err(GltfError::ExternalBuffer(uri.to_owned())))
+}
+
+fn decode_gltf_with(file: &[u8], read_uri: impl Fn(&str) -> Result<Vec<u8>, GltfError>) -> Result<GltfDecoded, GltfError> {
 
 	// figure out if file is binary
 	let is_binary = if let Some((header, _)) = file.split_first_chunk::<12>() {
@@ -237,7 +310,7 @@ pub fn decode_gltf(path: std::path::PathBuf) -> Result<GltfDecoded, GltfError> {
 		(json, internal_buffer)
 	} else {(
 		serde_json::from_str::<JSONGltf>(
-			std::str::from_utf8(&file).map_err(|_| GltfError::InvalidData)?
+			std::str::from_utf8(file).map_err(|_| GltfError::InvalidData)?
 		).map_err(|e| GltfError::InvalidJSON(e))?,
 		None,
 	)};
@@ -246,16 +319,7 @@ pub fn decode_gltf(path: std::path::PathBuf) -> Result<GltfDecoded, GltfError> {
 	let accessors = Accessors {
 		buffers: json.buffers.unwrap_or_default().into_iter().map(|buffer| {
 			if let Some(uri) = &buffer.uri {
-				let path = std::path::PathBuf::try_from(uri).map_err(|_| GltfError::InvalidURI(uri.clone()))?;
-				if path.is_absolute() {
-					return Err(GltfError::AbsoluteURI(path));
-				}
-				if path.components().find(|c| *c == std::path::Component::ParentDir).is_some() {
-					return Err(GltfError::ParentDirURI(path));
-				}
-				let mut full_path = path_parent.clone();
-				full_path.push(path);
-				Ok(std::fs::read(full_path)?)
+				read_uri(uri)
 			} else if let Some(buffer) = internal_buffer.take() {
 				Ok(buffer.to_vec())
 			} else {
@@ -268,14 +332,14 @@ pub fn decode_gltf(path: std::path::PathBuf) -> Result<GltfDecoded, GltfError> {
 
 	// materials
 	let materials = json.materials.unwrap_or_default().into_iter().map(|material| {
-		Rc::new(Material {
+		Arc::new(Material {
 			name: material.name,
 		})
 	}).collect::<Vec<_>>();
 	
 	// meshes
 	let meshes = json.meshes.unwrap_or_default().into_iter().map(|mesh| {
-		Ok(Rc::new(Mesh {
+		Ok(Arc::new(Mesh {
 			primitives: mesh.primitives.into_iter().map(|primitive| {
 				let positions = primitive.attributes.get("POSITION").map(|i|
 					accessors.convert(*i, |v| v)
@@ -378,7 +442,7 @@ pub fn decode_gltf(path: std::path::PathBuf) -> Result<GltfDecoded, GltfError> {
 	}
 
 	let scenes = json.scenes.iter().flatten().map(|scene| {
-		Ok(Rc::new(Scene {
+		Ok(Arc::new(Scene {
 			name: scene.name.clone(),
 			root_nodes: scene.nodes.iter().flatten().map(|i|
 				validate_index(&nodes_json, *i)
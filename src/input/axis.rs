@@ -0,0 +1,162 @@
+use glam::*;
+
+use super::{ButtonMapping, GamepadAxis, GamepadStick};
+
+/// Where the value of an axis action comes from.
+///
+/// One dimensional sources only set the x component.
+/// Positive y is down for every two dimensional source, to match gamepad sticks and the mouse.
+#[derive(Clone, Copy, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub enum AxisSource {
+	/// An axis on whichever connected gamepad has it pushed the furthest.
+	Gamepad(GamepadAxis),
+	/// An axis on the gamepad in a specific player slot.
+	PlayerGamepad(usize, GamepadAxis),
+	/// Both axes of a stick on whichever connected gamepad has it pushed the furthest.
+	GamepadStick(GamepadStick),
+	/// Both axes of a stick on the gamepad in a specific player slot.
+	PlayerGamepadStick(usize, GamepadStick),
+	/// Horizontal [mouse movement](super::InputCtx::mouse_delta) in screen coordinates.
+	MouseX,
+	/// Vertical [mouse movement](super::InputCtx::mouse_delta) in screen coordinates.
+	MouseY,
+	/// [Mouse movement](super::InputCtx::mouse_delta) in screen coordinates.
+	MouseMotion,
+	/// [Mouse wheel](super::InputCtx::wheel_delta) scrolling.
+	MouseWheel,
+	/// -1 while `negative` is held and 1 while `positive` is held, such as for A and D keys.
+	Buttons { negative: ButtonMapping, positive: ButtonMapping },
+	/// Four buttons combined into a direction, such as for WASD keys.
+	Buttons2D { up: ButtonMapping, down: ButtonMapping, left: ButtonMapping, right: ButtonMapping },
+}
+
+impl AxisSource {
+	/// Whether values from this source stay within a length of about 1, unlike mouse movement which has no maximum.
+	pub fn is_bounded(&self) -> bool {
+		!matches!(self, Self::MouseX | Self::MouseY | Self::MouseMotion | Self::MouseWheel)
+	}
+}
+
+/// Binding of an axis action to a source, with settings for how its value is processed.
+///
+/// When deserialized, settings that are left out get the same values as in [`AxisMapping::new`].
+///
+/// ```
+/// # use isopod::input::*;
+/// let mapping = AxisMapping {
+///     deadzone: 0.2,
+///     curve: 2.,
+///     ..AxisMapping::new(AxisSource::GamepadStick(GamepadStick::Left))
+/// };
+/// ```
+#[derive(Clone, Copy, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub struct AxisMapping {
+	pub source: AxisSource,
+	/// Values with a magnitude below this are treated as 0.
+	///
+	/// For [bounded](AxisSource::is_bounded) sources such as sticks, the rest of the range is stretched to start from 0, and a deadzone of 1 or more ignores the source.
+	/// Mouse sources are left as they are above the deadzone.
+	/// For two dimensional sources, this applies to the length of the value rather than each axis separately.
+	#[serde(default)]
+	pub deadzone: f32,
+	/// Exponent applied to the magnitude of the value after the deadzone.
+	///
+	/// 1 is linear, while higher values give finer control near the center of a stick.
+	/// For mouse movement, values above 1 act as acceleration.
+	/// Values of 0 or below are treated as 1.
+	#[serde(default = "one")]
+	pub curve: f32,
+	/// Multiplier applied last.
+	#[serde(default = "one")]
+	pub sensitivity: f32,
+	#[serde(default)]
+	pub invert_x: bool,
+	#[serde(default)]
+	pub invert_y: bool,
+}
+
+fn one() -> f32 {
+	1.
+}
+
+impl AxisMapping {
+	/// Creates a mapping with no deadzone, a linear curve and a sensitivity of 1.
+	pub fn new(source: AxisSource) -> Self {
+		Self { source, deadzone: 0., curve: 1., sensitivity: 1., invert_x: false, invert_y: false }
+	}
+
+	pub(crate) fn apply(&self, raw: Vec2) -> Vec2 {
+		let length = raw.length();
+		let deadzone = self.deadzone.max(0.);
+		let curve = if self.curve > 0. { self.curve } else { 1. };
+		let mut value = if length <= deadzone || length == 0. || (self.source.is_bounded() && deadzone >= 1.) {
+			Vec2::ZERO
+		} else {
+			let stretched = if self.source.is_bounded() { (length - deadzone) / (1. - deadzone) } else { length };
+			raw / length * stretched.powf(curve)
+		};
+		if self.invert_x {
+			value.x = -value.x;
+		}
+		if self.invert_y {
+			value.y = -value.y;
+		}
+		value * self.sensitivity
+	}
+}
+
+impl From<AxisSource> for AxisMapping {
+	fn from(source: AxisSource) -> Self {
+		Self::new(source)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn stick(deadzone: f32, curve: f32) -> AxisMapping {
+		AxisMapping { deadzone, curve, ..AxisMapping::new(AxisSource::GamepadStick(GamepadStick::Left)) }
+	}
+
+	fn assert_near(a: Vec2, b: Vec2) {
+		assert!(a.distance(b) < 1e-5, "{a} != {b}");
+	}
+
+	#[test]
+	fn deadzone() {
+		let mapping = stick(0.2, 1.);
+		assert_eq!(mapping.apply(vec2(0.1, -0.1)), Vec2::ZERO);
+		// the rest of the range is stretched to start from 0
+		assert_near(mapping.apply(vec2(0.6, 0.)), vec2(0.5, 0.));
+		assert_near(mapping.apply(vec2(0., -1.)), vec2(0., -1.));
+		assert_eq!(stick(1., 1.).apply(vec2(1., 1.)), Vec2::ZERO);
+		assert_eq!(stick(2., 1.).apply(vec2(1., 1.)), Vec2::ZERO);
+		assert_near(stick(-1., 1.).apply(vec2(0.5, 0.)), vec2(0.5, 0.));
+	}
+
+	#[test]
+	fn mouse_deadzone() {
+		let mapping = AxisMapping { deadzone: 2., ..AxisMapping::new(AxisSource::MouseMotion) };
+		assert_eq!(mapping.apply(vec2(1., 1.)), Vec2::ZERO);
+		// mouse movement isn't stretched, so it stays in screen coordinates
+		assert_near(mapping.apply(vec2(30., -40.)), vec2(30., -40.));
+	}
+
+	#[test]
+	fn curve() {
+		assert_near(stick(0., 2.).apply(vec2(0.5, 0.)), vec2(0.25, 0.));
+		assert_near(stick(0., 2.).apply(vec2(0., -1.)), vec2(0., -1.));
+		for curve in [0., -1., f32::NAN] {
+			assert_near(stick(0., curve).apply(vec2(0.5, 0.)), vec2(0.5, 0.));
+		}
+	}
+
+	#[test]
+	fn inversion_and_sensitivity() {
+		let mapping = AxisMapping { invert_y: true, sensitivity: 2., ..stick(0., 1.) };
+		assert_near(mapping.apply(vec2(0.25, 0.5)), vec2(0.5, -1.));
+		let mapping = AxisMapping { invert_x: true, sensitivity: 0.5, ..AxisMapping::new(AxisSource::MouseX) };
+		assert_near(mapping.apply(vec2(10., 0.)), vec2(-5., 0.));
+	}
+}
@@ -0,0 +1,76 @@
+use std::{any::TypeId, collections::BTreeMap, path::{Path, PathBuf}};
+
+use super::{AxisMapping, ButtonMapping};
+
+/// Button and axis mappings of every action, keyed by [action name](super::InputCtx::name_action).
+///
+/// This is what gets saved to and loaded from a file when players change their controls:
+///
+/// ```
+/// # use isopod::input::*;
+/// let bindings = Bindings::from_ron(r#"(
+///     buttons: {
+///         "Jump": [LogicalKey(SPACE), Gamepad(A)],
+///         "Save": [Shortcut([Ctrl], S)],
+///     },
+///     axes: {
+///         "Move": [(source: GamepadStick(Left), deadzone: 0.2)],
+///     },
+/// )"#).unwrap();
+/// assert_eq!(bindings.buttons["Jump"].len(), 2);
+/// ```
+#[derive(Clone, PartialEq, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Bindings {
+	pub buttons: BTreeMap<String, Vec<ButtonMapping>>,
+	pub axes: BTreeMap<String, Vec<AxisMapping>>,
+}
+
+impl Bindings {
+	pub fn from_ron(ron: &str) -> Result<Self, BindingsError> {
+		Ok(ron::de::from_str(ron)?)
+	}
+
+	pub fn to_ron(&self) -> Result<String, BindingsError> {
+		Ok(ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?)
+	}
+
+	pub fn from_file(path: impl AsRef<Path>) -> Result<Self, BindingsError> {
+		let path = path.as_ref();
+		let ron = std::fs::read_to_string(path).map_err(|source| BindingsError::IoError { path: path.to_path_buf(), source })?;
+		Self::from_ron(&ron)
+	}
+
+	pub fn save(&self, path: impl AsRef<Path>) -> Result<(), BindingsError> {
+		let path = path.as_ref();
+		std::fs::write(path, self.to_ron()?).map_err(|source| BindingsError::IoError { path: path.to_path_buf(), source })
+	}
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BindingsError {
+	#[error("failed to access \"{}\"", path.display())]
+	IoError {
+		path: PathBuf,
+		#[source]
+		source: std::io::Error,
+	},
+	#[error("failed to parse bindings")]
+	ParseError(#[from] ron::error::SpannedError),
+	#[error("failed to serialize bindings")]
+	SerializeError(#[from] ron::Error),
+}
+
+/// Change to the bindings, which are applied in order at the start of the next frame.
+pub(crate) enum BindingCmd {
+	/// Gives an action its default name if it doesn't have one yet, which is its short type name unless another action has it, then its full type name.
+	Register(TypeId, String, &'static str),
+	Name(TypeId, String),
+	MapButton(TypeId, ButtonMapping),
+	MapAxis(TypeId, AxisMapping),
+	UnbindButton(ButtonMapping),
+	/// Replaces a button of an action with another in the same position, if the action has it.
+	Rebind(TypeId, ButtonMapping, ButtonMapping),
+	UnbindAll(TypeId),
+	Load(Bindings),
+}
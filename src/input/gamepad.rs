@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use sdl2::controller::{Axis, Button, GameController};
+
+use super::{ButtonState, InputCtx};
+
+/// Button on a gamepad, named after its position on an Xbox controller.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, serde::Serialize, serde::Deserialize)]
+pub enum GamepadButton {
+	/// Bottom face button.
+	A,
+	/// Right face button.
+	B,
+	/// Left face button.
+	X,
+	/// Top face button.
+	Y,
+	Back,
+	Guide,
+	Start,
+	LeftStick,
+	RightStick,
+	LeftShoulder,
+	RightShoulder,
+	DPadUp,
+	DPadDown,
+	DPadLeft,
+	DPadRight,
+}
+
+impl GamepadButton {
+	pub(crate) const ALL: [GamepadButton; 15] = [
+		Self::A, Self::B, Self::X, Self::Y, Self::Back, Self::Guide, Self::Start, Self::LeftStick, Self::RightStick,
+		Self::LeftShoulder, Self::RightShoulder, Self::DPadUp, Self::DPadDown, Self::DPadLeft, Self::DPadRight,
+	];
+	pub(crate) const COUNT: usize = Self::ALL.len();
+
+	pub(crate) fn from_sdl(button: Button) -> Option<Self> {
+		Some(match button {
+			Button::A => Self::A,
+			Button::B => Self::B,
+			Button::X => Self::X,
+			Button::Y => Self::Y,
+			Button::Back => Self::Back,
+			Button::Guide => Self::Guide,
+			Button::Start => Self::Start,
+			Button::LeftStick => Self::LeftStick,
+			Button::RightStick => Self::RightStick,
+			Button::LeftShoulder => Self::LeftShoulder,
+			Button::RightShoulder => Self::RightShoulder,
+			Button::DPadUp => Self::DPadUp,
+			Button::DPadDown => Self::DPadDown,
+			Button::DPadLeft => Self::DPadLeft,
+			Button::DPadRight => Self::DPadRight,
+			_ => return None,
+		})
+	}
+
+	pub(crate) fn index(self) -> usize {
+		self as usize
+	}
+}
+
+/// Analog stick or trigger on a gamepad.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, serde::Serialize, serde::Deserialize)]
+pub enum GamepadAxis {
+	LeftX,
+	/// Vertical position of the left stick, with positive values being down.
+	LeftY,
+	RightX,
+	/// Vertical position of the right stick, with positive values being down.
+	RightY,
+	LeftTrigger,
+	RightTrigger,
+}
+
+impl GamepadAxis {
+	pub(crate) const COUNT: usize = 6;
+
+	pub(crate) fn from_sdl(axis: Axis) -> Self {
+		match axis {
+			Axis::LeftX => Self::LeftX,
+			Axis::LeftY => Self::LeftY,
+			Axis::RightX => Self::RightX,
+			Axis::RightY => Self::RightY,
+			Axis::TriggerLeft => Self::LeftTrigger,
+			Axis::TriggerRight => Self::RightTrigger,
+		}
+	}
+
+	pub(crate) fn index(self) -> usize {
+		self as usize
+	}
+}
+
+/// Analog stick on a gamepad, which combines two [axes](GamepadAxis).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, serde::Serialize, serde::Deserialize)]
+pub enum GamepadStick {
+	Left,
+	Right,
+}
+
+impl GamepadStick {
+	pub(crate) fn axes(self) -> (GamepadAxis, GamepadAxis) {
+		match self {
+			Self::Left => (GamepadAxis::LeftX, GamepadAxis::LeftY),
+			Self::Right => (GamepadAxis::RightX, GamepadAxis::RightY),
+		}
+	}
+}
+
+/// Gamepad being connected to or disconnected from a player slot, see [`InputCtx::gamepad_events`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GamepadEvent {
+	Connected(usize),
+	Disconnected(usize),
+}
+
+/// State of the gamepad in a player slot.
+pub(crate) struct Gamepad {
+	/// SDL joystick instance id, which identifies the gamepad in events.
+	pub instance_id: u32,
+	pub name: String,
+	pub buttons: [ButtonState; GamepadButton::COUNT],
+	pub axes: [f32; GamepadAxis::COUNT],
+}
+
+impl Gamepad {
+	pub fn new(instance_id: u32, name: String) -> Self {
+		Self { instance_id, name, buttons: Default::default(), axes: [0.; GamepadAxis::COUNT] }
+	}
+}
+
+/// Opens game controllers as they are connected, which is needed for SDL to send their events.
+pub(crate) struct GamepadSys {
+	subsystem: Option<sdl2::GameControllerSubsystem>,
+	controllers: HashMap<u32, GameController>,
+}
+
+impl GamepadSys {
+	pub fn new(sdl: &sdl2::Sdl) -> Self {
+		// games still work without gamepads if the subsystem is unavailable
+		Self { subsystem: sdl.game_controller().ok(), controllers: HashMap::new() }
+	}
+
+	/// Creates a system without SDL, where gamepads are connected as their events arrive.
+	pub fn headless() -> Self {
+		Self { subsystem: None, controllers: HashMap::new() }
+	}
+
+	/// Opens or closes controllers as they are connected or disconnected, returning the instance id and name of any gamepad that was opened.
+	pub fn process_event(&mut self, input: &mut InputCtx, event: &sdl2::event::Event) -> Option<(u32, String)> {
+		use sdl2::event::Event;
+		match event {
+			Event::ControllerDeviceAdded { which, .. } => {
+				let controller = self.subsystem.as_ref()?.open(*which).ok()?;
+				let (instance_id, name) = (controller.instance_id(), controller.name());
+				input.connect_gamepad(instance_id, name.clone());
+				self.controllers.insert(instance_id, controller);
+				Some((instance_id, name))
+			},
+			Event::ControllerDeviceRemoved { which, .. } => {
+				self.controllers.remove(which);
+				None
+			},
+			_ => None,
+		}
+	}
+}
@@ -1,13 +1,22 @@
 
 use sdl2::keyboard::Keycode;
 use sdl2::keyboard::Scancode;
+use sdl2::keyboard::Mod;
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, serde::Serialize, serde::Deserialize)]
 pub enum Key {
 	A,B,C,D,E,F,G,H,I,J,K,L,M,N,O,P,Q,R,S,T,U,V,W,X,Y,Z,
-	F1,F2,F3,F4,F5,F6,F7,F8,F9,F10,F11,F12,
+	NUM_0,NUM_1,NUM_2,NUM_3,NUM_4,NUM_5,NUM_6,NUM_7,NUM_8,NUM_9,
+	F1,F2,F3,F4,F5,F6,F7,F8,F9,F10,F11,F12,F13,F14,F15,F16,F17,F18,F19,F20,F21,F22,F23,F24,
 	BACKSPACE,TAB,RETURN,ESCAPE,SPACE,
-	UP,LEFT,RIGHT,DOWN,INSERT,HOME,PAGEUP,PAGEDOWN,
+	MINUS,EQUALS,LEFTBRACKET,RIGHTBRACKET,BACKSLASH,SEMICOLON,QUOTE,BACKQUOTE,COMMA,PERIOD,SLASH,
+	UP,LEFT,RIGHT,DOWN,INSERT,DELETE,HOME,END,PAGEUP,PAGEDOWN,
+	CAPSLOCK,PRINTSCREEN,SCROLLLOCK,PAUSE,APPLICATION,
+	LSHIFT,RSHIFT,LCTRL,RCTRL,LALT,RALT,LGUI,RGUI,
+	NUMLOCKCLEAR,KP_0,KP_1,KP_2,KP_3,KP_4,KP_5,KP_6,KP_7,KP_8,KP_9,
+	KP_DIVIDE,KP_MULTIPLY,KP_MINUS,KP_PLUS,KP_ENTER,KP_PERIOD,KP_EQUALS,
+	MUTE,VOLUMEUP,VOLUMEDOWN,AUDIONEXT,AUDIOPREV,AUDIOSTOP,AUDIOPLAY,AUDIOMUTE,
 }
 
 macro_rules! one_to_one_mapping {
@@ -23,41 +32,155 @@ macro_rules! one_to_one_mapping {
 	};
 }
 
+macro_rules! renamed_mapping {
+	( $name:ident : $t:ty : $( $from:ident => $to:ident ),* $(,)*) => {
+		fn $name(v: $t) -> Option<Key> {
+			match v {
+				$(
+					<$t>:: $from => Some(Key:: $to),
+				)*
+				_ => None,
+			}
+		}
+	};
+}
+
 one_to_one_mapping!( keycode_one_to_one_mapping : Keycode :
 	A,B,C,D,E,F,G,H,I,J,K,L,M,N,O,P,Q,R,S,T,U,V,W,X,Y,Z,
-	F1,F2,F3,F4,F5,F6,F7,F8,F9,F10,F11,F12,
+	NUM_0,NUM_1,NUM_2,NUM_3,NUM_4,NUM_5,NUM_6,NUM_7,NUM_8,NUM_9,
+	F1,F2,F3,F4,F5,F6,F7,F8,F9,F10,F11,F12,F13,F14,F15,F16,F17,F18,F19,F20,F21,F22,F23,F24,
 	BACKSPACE,TAB,RETURN,ESCAPE,SPACE,
-	UP,LEFT,RIGHT,DOWN,INSERT,HOME,PAGEUP,PAGEDOWN,
+	MINUS,EQUALS,LEFTBRACKET,RIGHTBRACKET,BACKSLASH,SEMICOLON,QUOTE,BACKQUOTE,COMMA,PERIOD,SLASH,
+	UP,LEFT,RIGHT,DOWN,INSERT,DELETE,HOME,END,PAGEUP,PAGEDOWN,
+	CAPSLOCK,PRINTSCREEN,SCROLLLOCK,PAUSE,APPLICATION,
+	LSHIFT,RSHIFT,LCTRL,RCTRL,LALT,RALT,LGUI,RGUI,
+	NUMLOCKCLEAR,KP_0,KP_1,KP_2,KP_3,KP_4,KP_5,KP_6,KP_7,KP_8,KP_9,
+	KP_DIVIDE,KP_MULTIPLY,KP_MINUS,KP_PLUS,KP_ENTER,KP_PERIOD,KP_EQUALS,
+	MUTE,VOLUMEUP,VOLUMEDOWN,AUDIONEXT,AUDIOPREV,AUDIOSTOP,AUDIOPLAY,AUDIOMUTE,
 );
 
 one_to_one_mapping!( scancode_one_to_one_mapping : Scancode :
 	A,B,C,D,E,F,G,H,I,J,K,L,M,N,O,P,Q,R,S,T,U,V,W,X,Y,Z,
-	F1,F2,F3,F4,F5,F6,F7,F8,F9,F10,F11,F12,
+	F1,F2,F3,F4,F5,F6,F7,F8,F9,F10,F11,F12,F13,F14,F15,F16,F17,F18,F19,F20,F21,F22,F23,F24,
+);
+
+renamed_mapping!( scancode_renamed_mapping : Scancode :
+	Num0 => NUM_0, Num1 => NUM_1, Num2 => NUM_2, Num3 => NUM_3, Num4 => NUM_4,
+	Num5 => NUM_5, Num6 => NUM_6, Num7 => NUM_7, Num8 => NUM_8, Num9 => NUM_9,
+	Backspace => BACKSPACE, Tab => TAB, Return => RETURN, Escape => ESCAPE, Space => SPACE,
+	Minus => MINUS, Equals => EQUALS, LeftBracket => LEFTBRACKET, RightBracket => RIGHTBRACKET, Backslash => BACKSLASH,
+	Semicolon => SEMICOLON, Apostrophe => QUOTE, Grave => BACKQUOTE, Comma => COMMA, Period => PERIOD, Slash => SLASH,
+	Up => UP, Left => LEFT, Right => RIGHT, Down => DOWN,
+	Insert => INSERT, Delete => DELETE, Home => HOME, End => END, PageUp => PAGEUP, PageDown => PAGEDOWN,
+	CapsLock => CAPSLOCK, PrintScreen => PRINTSCREEN, ScrollLock => SCROLLLOCK, Pause => PAUSE, Application => APPLICATION,
+	LShift => LSHIFT, RShift => RSHIFT, LCtrl => LCTRL, RCtrl => RCTRL, LAlt => LALT, RAlt => RALT, LGui => LGUI, RGui => RGUI,
+	NumLockClear => NUMLOCKCLEAR, Kp0 => KP_0, Kp1 => KP_1, Kp2 => KP_2, Kp3 => KP_3, Kp4 => KP_4,
+	Kp5 => KP_5, Kp6 => KP_6, Kp7 => KP_7, Kp8 => KP_8, Kp9 => KP_9,
+	KpDivide => KP_DIVIDE, KpMultiply => KP_MULTIPLY, KpMinus => KP_MINUS, KpPlus => KP_PLUS,
+	KpEnter => KP_ENTER, KpPeriod => KP_PERIOD, KpEquals => KP_EQUALS,
+	Mute => MUTE, VolumeUp => VOLUMEUP, VolumeDown => VOLUMEDOWN,
+	AudioNext => AUDIONEXT, AudioPrev => AUDIOPREV, AudioStop => AUDIOSTOP, AudioPlay => AUDIOPLAY, AudioMute => AUDIOMUTE,
 );
 
 impl Key {
 	pub(crate) fn from_keycode(key: Keycode) -> Option<Key> {
-		match key {
-			key => keycode_one_to_one_mapping(key),
-		}
+		keycode_one_to_one_mapping(key)
 	}
 
 	pub(crate) fn from_scancode(key: Scancode) -> Option<Key> {
-		match key {
-			Scancode::Backspace => Some(Key::BACKSPACE),
-			Scancode::Tab => Some(Key::TAB),
-			Scancode::Return => Some(Key::RETURN),
-			Scancode::Escape => Some(Key::ESCAPE),
-			Scancode::Space => Some(Key::SPACE),
-			Scancode::Up => Some(Key::UP),
-			Scancode::Left => Some(Key::LEFT),
-			Scancode::Right => Some(Key::RIGHT),
-			Scancode::Down => Some(Key::DOWN),
-			Scancode::Insert => Some(Key::INSERT),
-			Scancode::Home => Some(Key::HOME),
-			Scancode::PageUp => Some(Key::PAGEUP),
-			Scancode::PageDown => Some(Key::PAGEDOWN),
-			key => scancode_one_to_one_mapping(key),
+		scancode_one_to_one_mapping(key).or_else(|| scancode_renamed_mapping(key))
+	}
+
+	pub(crate) fn is_modifier(self) -> bool {
+		matches!(self, Key::LSHIFT | Key::RSHIFT | Key::LCTRL | Key::RCTRL | Key::LALT | Key::RALT | Key::LGUI | Key::RGUI)
+	}
+}
+
+/// Set of modifier keys, where left and right versions of a key are treated the same.
+///
+/// Sets can be combined with `|`, such as `Modifiers::CTRL | Modifiers::SHIFT`.
+/// They are serialized as a list of names, such as `[Ctrl, Shift]`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(into = "Vec<ModifierKey>", from = "Vec<ModifierKey>")]
+pub struct Modifiers(u8);
+
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
+enum ModifierKey {
+	Shift,
+	Ctrl,
+	Alt,
+	Super,
+}
+
+impl ModifierKey {
+	const ALL: [(ModifierKey, Modifiers); 4] = [
+		(Self::Shift, Modifiers::SHIFT),
+		(Self::Ctrl, Modifiers::CTRL),
+		(Self::Alt, Modifiers::ALT),
+		(Self::Super, Modifiers::SUPER),
+	];
+}
+
+impl From<Modifiers> for Vec<ModifierKey> {
+	fn from(modifiers: Modifiers) -> Self {
+		ModifierKey::ALL.into_iter().filter(|(_, modifier)| modifiers.contains(*modifier)).map(|(key, _)| key).collect()
+	}
+}
+
+impl From<Vec<ModifierKey>> for Modifiers {
+	fn from(keys: Vec<ModifierKey>) -> Self {
+		keys.into_iter().fold(Modifiers::NONE, |modifiers, key| modifiers | ModifierKey::ALL[key as usize].1)
+	}
+}
+
+impl Modifiers {
+	pub const NONE: Self = Self(0);
+	pub const SHIFT: Self = Self(1);
+	pub const CTRL: Self = Self(2);
+	pub const ALT: Self = Self(4);
+	/// The Windows, Command or Super key.
+	pub const SUPER: Self = Self(8);
+
+	pub fn contains(self, other: Self) -> bool {
+		self.0 & other.0 == other.0
+	}
+
+	pub fn shift(self) -> bool {
+		self.contains(Self::SHIFT)
+	}
+
+	pub fn ctrl(self) -> bool {
+		self.contains(Self::CTRL)
+	}
+
+	pub fn alt(self) -> bool {
+		self.contains(Self::ALT)
+	}
+
+	pub fn is_super(self) -> bool {
+		self.contains(Self::SUPER)
+	}
+
+	pub(crate) fn from_sdl(keymod: Mod) -> Self {
+		let mut modifiers = Self::NONE;
+		for (sdl, modifier) in [
+			(Mod::LSHIFTMOD | Mod::RSHIFTMOD, Self::SHIFT),
+			(Mod::LCTRLMOD | Mod::RCTRLMOD, Self::CTRL),
+			(Mod::LALTMOD | Mod::RALTMOD, Self::ALT),
+			(Mod::LGUIMOD | Mod::RGUIMOD, Self::SUPER),
+		] {
+			if keymod.intersects(sdl) {
+				modifiers = modifiers | modifier;
+			}
 		}
+		modifiers
 	}
-}
\ No newline at end of file
+}
+
+impl std::ops::BitOr for Modifiers {
+	type Output = Self;
+
+	fn bitor(self, rhs: Self) -> Self {
+		Self(self.0 | rhs.0)
+	}
+}
@@ -1,12 +1,27 @@
 
-use std::{any::TypeId, collections::HashMap};
+use std::{any::TypeId, cell::Cell, collections::{HashMap, HashSet}};
 
 use glam::*;
 
 mod key;
-pub use key::Key;
+pub use key::{Key, Modifiers};
 
-use crate::util::BufferCell;
+mod mouse;
+pub use mouse::MouseButton;
+
+mod gamepad;
+pub use gamepad::{GamepadAxis, GamepadButton, GamepadEvent, GamepadStick};
+pub(crate) use gamepad::GamepadSys;
+use gamepad::Gamepad;
+
+mod axis;
+pub use axis::{AxisMapping, AxisSource};
+
+mod bindings;
+pub use bindings::{Bindings, BindingsError};
+use bindings::BindingCmd;
+
+use crate::util::{BufferCell, short_type_name};
 
 #[derive(Default)]
 struct ButtonState {
@@ -15,6 +30,19 @@ struct ButtonState {
 	released_this_frame: bool,
 }
 
+impl ButtonState {
+	fn set(&mut self, pressed: bool) {
+		if self.pressed != pressed {
+			self.pressed = pressed;
+			if pressed {
+				self.pressed_this_frame = true;
+			} else {
+				self.released_this_frame = true;
+			}
+		}
+	}
+}
+
 #[derive(Default)]
 pub struct TextInput {
 	pub n_backspaces: usize,
@@ -24,39 +52,98 @@ pub struct TextInput {
 
 pub struct InputCtx {
 	pub text_input: TextInput,
-	new_mappings: BufferCell<(ButtonMapping, TypeId)>,
+	binding_cmds: BufferCell<BindingCmd>,
+	injected_events: BufferCell<sdl2::event::Event>,
+	action_names: HashMap<TypeId, String>,
 	mappings: HashMap<ButtonMapping, TypeId>,
+	/// Button mappings of each action in the order they were added, which is kept even if it becomes empty.
+	button_mappings: HashMap<TypeId, Vec<ButtonMapping>>,
 	button_states: HashMap<TypeId, ButtonState>,
+	/// Every button that is held down, whether or not it is mapped to an action.
+	held: HashSet<ButtonMapping>,
+	axis_mappings: HashMap<TypeId, Vec<AxisMapping>>,
+	listening: Cell<bool>,
+	heard_input: Option<ButtonMapping>,
+	/// Modifier key pressed with no other button since, which is heard if it is released on its own.
+	lone_modifier: Option<ButtonMapping>,
+	modifiers: Modifiers,
+	mouse_buttons: [ButtonState; 5],
+	mouse_position: Vec2,
+	mouse_delta: Vec2,
+	wheel_delta: Vec2,
+	/// Player slots, which keep their index when a gamepad is disconnected so other players don't move.
+	gamepads: Vec<Option<Gamepad>>,
+	gamepad_events: Vec<GamepadEvent>,
+	/// Ratio between the size of the window canvas in pixels and the size of the window in screen coordinates.
+	pub(crate) canvas_scale: Vec2,
+	/// Whether relative mouse mode is enabled, in which case the cursor position is left alone.
+	pub(crate) relative_mouse: bool,
 }
 
-#[derive(PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, serde::Serialize, serde::Deserialize)]
 pub enum ButtonMapping {
 	LogicalKey(Key),
 	PhysicalKey(Key),
+	/// A key (matched like [`PhysicalKey`](ButtonMapping::PhysicalKey)) pressed while exactly the given modifiers are held, such as Ctrl+S.
+	/// 
+	/// Modifiers that aren't part of the shortcut prevent it from being pressed, so Ctrl+Shift+S doesn't trigger Ctrl+S.
+	Shortcut(Modifiers, Key),
+	Mouse(MouseButton),
+	/// A button on any connected gamepad.
+	Gamepad(GamepadButton),
+	/// A button on the gamepad in a specific player slot.
+	PlayerGamepad(usize, GamepadButton),
 }
 
 impl InputCtx {
 	pub(crate) fn new() -> Self {
 		Self {
-			new_mappings: BufferCell::new(),
+			binding_cmds: BufferCell::new(),
+			injected_events: BufferCell::new(),
+			action_names: HashMap::new(),
 			mappings: HashMap::new(),
+			button_mappings: HashMap::new(),
 			button_states: HashMap::new(),
+			held: HashSet::new(),
+			axis_mappings: HashMap::new(),
+			listening: Cell::new(false),
+			heard_input: None,
+			lone_modifier: None,
+			modifiers: Modifiers::NONE,
+			mouse_buttons: Default::default(),
+			mouse_position: Vec2::ZERO,
+			mouse_delta: Vec2::ZERO,
+			wheel_delta: Vec2::ZERO,
+			gamepads: vec![],
+			gamepad_events: vec![],
+			canvas_scale: Vec2::ONE,
+			relative_mouse: false,
 			text_input: TextInput::default(),
 		}
 	}
 
-	pub(crate) fn start_update(&mut self) {
-		for (mapping, id) in self.new_mappings.get_mut().drain(..) {
-			self.mappings.insert(mapping, id);
-			self.button_states.insert(id, ButtonState::default());
-		}
-		for state in self.button_states.values_mut() {
+	/// Applies binding changes and starts a new frame of input, returning warnings about changes that couldn't be applied as requested.
+	pub(crate) fn start_update(&mut self) -> Vec<String> {
+		let cmds = std::mem::take(self.binding_cmds.get_mut());
+		let warnings = cmds.into_iter().filter_map(|cmd| self.apply_binding_cmd(cmd)).collect();
+		let gamepad_buttons = self.gamepads.iter_mut().flatten().flat_map(|gamepad| &mut gamepad.buttons);
+		for state in self.button_states.values_mut().chain(&mut self.mouse_buttons).chain(gamepad_buttons) {
 			state.pressed_this_frame = false;
 			state.released_this_frame = false;
 		}
+		self.mouse_delta = Vec2::ZERO;
+		self.wheel_delta = Vec2::ZERO;
+		self.gamepad_events.clear();
+		self.heard_input = None;
 		self.text_input.n_backspaces = 0;
 		self.text_input.enter = false;
 		self.text_input.text.clear();
+		warnings
+	}
+
+	/// Takes the events injected during the last frame, which are processed along with the window's events.
+	pub(crate) fn take_injected_events(&mut self) -> Vec<sdl2::event::Event> {
+		std::mem::take(self.injected_events.get_mut())
 	}
 
 	pub(crate) fn process_event(&mut self, event: sdl2::event::Event) {
@@ -65,7 +152,8 @@ impl InputCtx {
 			Event::TextInput { text, .. } => {
 				self.text_input.text.push_str(&text);
 			},
-			Event::KeyDown { keycode, scancode, repeat, .. } => {
+			Event::KeyDown { keycode, scancode, keymod, repeat, .. } => {
+				self.modifiers = Modifiers::from_sdl(keymod);
 				if scancode == Some(sdl2::keyboard::Scancode::Backspace) {
 					if self.text_input.text.pop().is_none() {
 						self.text_input.n_backspaces += 1;
@@ -74,44 +162,309 @@ impl InputCtx {
 					self.text_input.enter = true;
 				}
 				if !repeat {
-					if let Some(state) = keycode.map(|k| Key::from_keycode(k)).flatten()
-						.map(|k| self.mappings.get(&ButtonMapping::PhysicalKey(k))).flatten()
-						.map(|id| self.button_states.get_mut(id)).flatten()
-					{
-						state.pressed = true;
-						state.pressed_this_frame = true;
+					let physical = keycode.and_then(Key::from_keycode);
+					let logical = scancode.and_then(Key::from_scancode);
+					if let Some(k) = physical {
+						self.set_mapped(ButtonMapping::PhysicalKey(k), true);
+						self.set_mapped(ButtonMapping::Shortcut(self.modifiers, k), true);
 					}
-					if let Some(state) = scancode.map(|k| Key::from_scancode(k)).flatten()
-						.map(|k| self.mappings.get(&ButtonMapping::LogicalKey(k))).flatten()
-						.map(|id| self.button_states.get_mut(id)).flatten()
-					{
-						state.pressed = true;
-						state.pressed_this_frame = true;
+					if let Some(k) = logical {
+						self.set_mapped(ButtonMapping::LogicalKey(k), true);
+					}
+					let heard = match (physical, logical) {
+						// modifier keys on their own can be bound, but otherwise they are part of a shortcut
+						(Some(k), _) if self.modifiers != Modifiers::NONE && !k.is_modifier() => Some(ButtonMapping::Shortcut(self.modifiers, k)),
+						(_, Some(k)) => Some(ButtonMapping::LogicalKey(k)),
+						(Some(k), None) => Some(ButtonMapping::PhysicalKey(k)),
+						(None, None) => None,
+					};
+					// modifiers might be the start of a shortcut, so they are only heard once released
+					if physical.or(logical).is_some_and(Key::is_modifier) {
+						self.lone_modifier = heard;
+					} else if let Some(mapping) = heard {
+						self.hear(mapping);
 					}
 				}
 			},
-			Event::KeyUp { keycode, scancode, repeat, .. } => {
+			Event::KeyUp { keycode, scancode, keymod, repeat, .. } => {
+				self.modifiers = Modifiers::from_sdl(keymod);
 				if !repeat {
-					if let Some(state) = keycode.map(|k| Key::from_keycode(k)).flatten()
-						.map(|k| self.mappings.get(&ButtonMapping::PhysicalKey(k))).flatten()
-						.map(|id| self.button_states.get_mut(id)).flatten()
-					{
-						state.pressed = false;
-						state.released_this_frame = true;
+					let physical = keycode.and_then(Key::from_keycode).map(ButtonMapping::PhysicalKey);
+					let logical = scancode.and_then(Key::from_scancode).map(ButtonMapping::LogicalKey);
+					if let Some(mapping) = self.lone_modifier.filter(|mapping| [physical, logical].contains(&Some(*mapping))) {
+						self.hear(mapping);
+					}
+					if let Some(k) = keycode.and_then(Key::from_keycode) {
+						self.set_mapped(ButtonMapping::PhysicalKey(k), false);
+						// shortcuts stay held until their key is released, even if the modifiers are released first
+						let shortcuts = self.held.iter().filter(|mapping| matches!(mapping, ButtonMapping::Shortcut(_, key) if *key == k)).copied().collect::<Vec<_>>();
+						for shortcut in shortcuts {
+							self.set_mapped(shortcut, false);
+						}
 					}
-					if let Some(state) = scancode.map(|k| Key::from_scancode(k)).flatten()
-						.map(|k| self.mappings.get(&ButtonMapping::LogicalKey(k))).flatten()
-						.map(|id| self.button_states.get_mut(id)).flatten()
-					{
-						state.pressed = false;
-						state.released_this_frame = true;
+					if let Some(k) = scancode.and_then(Key::from_scancode) {
+						self.set_mapped(ButtonMapping::LogicalKey(k), false);
 					}
 				}
 			},
+			Event::MouseMotion { x, y, xrel, yrel, .. } => {
+				self.set_mouse_position(x, y);
+				self.mouse_delta += vec2(xrel as f32, yrel as f32);
+			},
+			Event::MouseButtonDown { mouse_btn, x, y, .. } => {
+				self.set_mouse_position(x, y);
+				if let Some(button) = MouseButton::from_sdl(mouse_btn) {
+					self.set_mouse_button(button, true);
+				}
+			},
+			Event::MouseButtonUp { mouse_btn, x, y, .. } => {
+				self.set_mouse_position(x, y);
+				if let Some(button) = MouseButton::from_sdl(mouse_btn) {
+					self.set_mouse_button(button, false);
+				}
+			},
+			Event::MouseWheel { precise_x, precise_y, direction, .. } => {
+				let delta = vec2(precise_x, precise_y);
+				self.wheel_delta += if direction == sdl2::mouse::MouseWheelDirection::Flipped { -delta } else { delta };
+			},
+			Event::ControllerButtonDown { which, button, .. } => {
+				let player = self.gamepad_slot(which);
+				if let Some(button) = GamepadButton::from_sdl(button) {
+					self.set_gamepad_button(player, button, true);
+				}
+			},
+			Event::ControllerButtonUp { which, button, .. } => {
+				let player = self.gamepad_slot(which);
+				if let Some(button) = GamepadButton::from_sdl(button) {
+					self.set_gamepad_button(player, button, false);
+				}
+			},
+			Event::ControllerAxisMotion { which, axis, value, .. } => {
+				let player = self.gamepad_slot(which);
+				if let Some(Some(gamepad)) = self.gamepads.get_mut(player) {
+					gamepad.axes[GamepadAxis::from_sdl(axis).index()] = (value as f32 / i16::MAX as f32).clamp(-1., 1.);
+				}
+			},
+			Event::ControllerDeviceRemoved { which, .. } => {
+				self.disconnect_gamepad(which);
+			},
+			Event::Window { win_event: sdl2::event::WindowEvent::FocusLost, .. } => {
+				self.release_all();
+			},
 			_ => {},
 		}
 	}
 
+	/// Releases every button and centers every gamepad axis, since their release events are missed while the window is unfocused.
+	fn release_all(&mut self) {
+		let players = self.gamepads().collect::<Vec<_>>();
+		for player in players {
+			for button in GamepadButton::ALL {
+				self.set_gamepad_button(player, button, false);
+			}
+			self.gamepads[player].as_mut().unwrap().axes = [0.; GamepadAxis::COUNT];
+		}
+		for state in &mut self.mouse_buttons {
+			state.set(false);
+		}
+		let held = self.held.iter().copied().collect::<Vec<_>>();
+		for mapping in held {
+			self.set_mapped(mapping, false);
+		}
+		self.modifiers = Modifiers::NONE;
+	}
+
+	fn set_mapped(&mut self, mapping: ButtonMapping, pressed: bool) {
+		if pressed {
+			self.held.insert(mapping);
+		} else {
+			self.held.remove(&mapping);
+		}
+		if let Some(state) = self.mappings.get(&mapping).and_then(|id| self.button_states.get_mut(id)) {
+			state.set(pressed);
+		}
+	}
+
+	fn set_mouse_button(&mut self, button: MouseButton, pressed: bool) {
+		self.mouse_buttons[button.index()].set(pressed);
+		self.set_mapped(ButtonMapping::Mouse(button), pressed);
+		if pressed {
+			self.hear(ButtonMapping::Mouse(button));
+		}
+	}
+
+	fn set_mouse_position(&mut self, x: i32, y: i32) {
+		// the hidden cursor still moves in relative mode, but its position is meaningless
+		if !self.relative_mouse {
+			self.mouse_position = vec2(x as f32, y as f32);
+		}
+	}
+
+	fn set_gamepad_button(&mut self, player: usize, button: GamepadButton, pressed: bool) {
+		if let Some(Some(gamepad)) = self.gamepads.get_mut(player) {
+			gamepad.buttons[button.index()].set(pressed);
+		}
+		// any gamepad counts as pressed as long as one of them is held down
+		let any_pressed = self.gamepads.iter().flatten().any(|gamepad| gamepad.buttons[button.index()].pressed);
+		self.set_mapped(ButtonMapping::Gamepad(button), any_pressed);
+		self.set_mapped(ButtonMapping::PlayerGamepad(player, button), pressed);
+		if pressed {
+			self.hear(ButtonMapping::Gamepad(button));
+		}
+	}
+
+	fn hear(&mut self, mapping: ButtonMapping) {
+		self.lone_modifier = None;
+		if self.listening.replace(false) {
+			self.heard_input = Some(mapping);
+		}
+	}
+
+	fn apply_binding_cmd(&mut self, cmd: BindingCmd) -> Option<String> {
+		match cmd {
+			BindingCmd::Register(id, short_name, full_name) => {
+				if self.action_names.contains_key(&id) {
+					return None;
+				}
+				// names have to be unique so bindings can be saved and loaded
+				if self.action_names.values().any(|name| *name == short_name) {
+					self.action_names.insert(id, full_name.to_owned());
+					return Some(format!("another action is already named \"{}\", so {} is named after its full path instead", short_name, full_name));
+				}
+				self.action_names.insert(id, short_name);
+			},
+			BindingCmd::Name(id, name) => {
+				if self.action_names.iter().any(|(other, other_name)| *other != id && *other_name == name) {
+					return Some(format!("can't name an action \"{}\" since another action already has that name", name));
+				}
+				self.action_names.insert(id, name);
+			},
+			BindingCmd::MapButton(id, mapping) => self.bind_button(id, mapping),
+			BindingCmd::MapAxis(id, mapping) => {
+				self.axis_mappings.entry(id).or_default().push(mapping);
+			},
+			BindingCmd::UnbindButton(mapping) => self.unbind_button_now(mapping),
+			BindingCmd::Rebind(id, old, new) => self.rebind_button(id, old, new),
+			BindingCmd::UnbindAll(id) => {
+				self.unbind_buttons_of(id);
+				if let Some(mappings) = self.axis_mappings.get_mut(&id) {
+					mappings.clear();
+				}
+			},
+			BindingCmd::Load(bindings) => {
+				let ids = self.action_names.iter().map(|(id, name)| (name.clone(), *id)).collect::<HashMap<_, _>>();
+				for (name, mappings) in bindings.buttons {
+					if let Some(id) = ids.get(&name) {
+						self.unbind_buttons_of(*id);
+						for mapping in mappings {
+							self.bind_button(*id, mapping);
+						}
+					}
+				}
+				for (name, mappings) in bindings.axes {
+					if let Some(id) = ids.get(&name) {
+						self.axis_mappings.insert(*id, mappings);
+					}
+				}
+			},
+		}
+		None
+	}
+
+	fn bind_button(&mut self, id: TypeId, mapping: ButtonMapping) {
+		// a button can only trigger one action, so it is taken away from any other action first
+		self.unbind_button_now(mapping);
+		self.mappings.insert(mapping, id);
+		self.button_mappings.entry(id).or_default().push(mapping);
+		self.button_states.entry(id).or_default();
+	}
+
+	fn unbind_button_now(&mut self, mapping: ButtonMapping) {
+		let Some(id) = self.mappings.remove(&mapping) else {
+			return;
+		};
+		if let Some(mappings) = self.button_mappings.get_mut(&id) {
+			mappings.retain(|m| *m != mapping);
+		}
+		// release the action so it doesn't get stuck when a held button is unbound
+		if self.held.contains(&mapping) {
+			if let Some(state) = self.button_states.get_mut(&id) {
+				state.set(false);
+			}
+		}
+	}
+
+	fn rebind_button(&mut self, id: TypeId, old: ButtonMapping, new: ButtonMapping) {
+		if self.mappings.get(&old) != Some(&id) || old == new {
+			return;
+		}
+		self.unbind_button_now(new);
+		let Some(index) = self.button_mappings.get(&id).and_then(|mappings| mappings.iter().position(|m| *m == old)) else {
+			return;
+		};
+		self.mappings.remove(&old);
+		// release the action so it doesn't get stuck when a held button is unbound
+		if self.held.contains(&old) {
+			if let Some(state) = self.button_states.get_mut(&id) {
+				state.set(false);
+			}
+		}
+		self.button_mappings.get_mut(&id).unwrap()[index] = new;
+		self.mappings.insert(new, id);
+	}
+
+	fn unbind_buttons_of(&mut self, id: TypeId) {
+		let mappings = self.button_mappings.get(&id).cloned().unwrap_or_default();
+		for mapping in mappings {
+			self.unbind_button_now(mapping);
+		}
+	}
+
+	fn find_gamepad(&self, instance_id: u32) -> Option<usize> {
+		self.gamepads.iter().position(|gamepad| gamepad.as_ref().is_some_and(|gamepad| gamepad.instance_id == instance_id))
+	}
+
+	/// Returns the player slot of a gamepad, connecting it first if events arrive from a gamepad that hasn't been connected (such as in tests).
+	fn gamepad_slot(&mut self, instance_id: u32) -> usize {
+		match self.find_gamepad(instance_id) {
+			Some(player) => player,
+			None => self.connect_gamepad(instance_id, String::new()),
+		}
+	}
+
+	/// Puts a gamepad in the first free player slot.
+	pub(crate) fn connect_gamepad(&mut self, instance_id: u32, name: String) -> usize {
+		if let Some(player) = self.find_gamepad(instance_id) {
+			self.gamepads[player].as_mut().unwrap().name = name;
+			return player;
+		}
+		let gamepad = Some(Gamepad::new(instance_id, name));
+		let player = match self.gamepads.iter().position(Option::is_none) {
+			Some(player) => {
+				self.gamepads[player] = gamepad;
+				player
+			},
+			None => {
+				self.gamepads.push(gamepad);
+				self.gamepads.len() - 1
+			},
+		};
+		self.gamepad_events.push(GamepadEvent::Connected(player));
+		player
+	}
+
+	fn disconnect_gamepad(&mut self, instance_id: u32) {
+		let Some(player) = self.find_gamepad(instance_id) else {
+			return;
+		};
+		// release anything still held so actions don't get stuck
+		for button in GamepadButton::ALL {
+			self.set_gamepad_button(player, button, false);
+		}
+		self.gamepads[player] = None;
+		self.gamepad_events.push(GamepadEvent::Disconnected(player));
+	}
+
 	pub fn is_pressed<Action: 'static>(&self) -> bool {
 		self.button_states.get(&TypeId::of::<Action>()).map(|state| state.pressed).unwrap_or(false)
 	}
@@ -124,7 +477,244 @@ impl InputCtx {
 		self.button_states.get(&TypeId::of::<Action>()).map(|state| state.released_this_frame).unwrap_or(false)
 	}
 
+	/// Modifier keys that are currently held.
+	pub fn modifiers(&self) -> Modifiers {
+		self.modifiers
+	}
+
+	pub fn is_mouse_pressed(&self, button: MouseButton) -> bool {
+		self.mouse_buttons[button.index()].pressed
+	}
+
+	pub fn is_mouse_just_pressed(&self, button: MouseButton) -> bool {
+		self.mouse_buttons[button.index()].pressed_this_frame
+	}
+
+	pub fn is_mouse_just_released(&self, button: MouseButton) -> bool {
+		self.mouse_buttons[button.index()].released_this_frame
+	}
+
+	/// Position of the cursor (in screen coordinates) relative to the top left corner of the window.
+	/// 
+	/// This is not updated while [relative mouse mode](crate::EngineCtx::set_relative_mouse_mode) is enabled.
+	pub fn mouse_position(&self) -> Vec2 {
+		self.mouse_position
+	}
+
+	/// Position of the cursor in pixels on the [window canvas](crate::gfx::GfxCtx::window_canvas).
+	/// 
+	/// This differs from [`mouse_position`](InputCtx::mouse_position) on high DPI displays.
+	pub fn mouse_canvas_position(&self) -> Vec2 {
+		self.mouse_position * self.canvas_scale
+	}
+
+	/// How far (in screen coordinates) the mouse has moved since the last frame.
+	/// 
+	/// This keeps working in [relative mouse mode](crate::EngineCtx::set_relative_mouse_mode), which makes it suitable for camera controls.
+	pub fn mouse_delta(&self) -> Vec2 {
+		self.mouse_delta
+	}
+
+	/// How far the mouse wheel has scrolled since the last frame, with positive values being right and away from the user.
+	pub fn wheel_delta(&self) -> Vec2 {
+		self.wheel_delta
+	}
+
+	/// Returns an iterator over the player slots that have a gamepad connected.
+	pub fn gamepads(&self) -> impl Iterator<Item = usize> + '_ {
+		self.gamepads.iter().enumerate().filter(|(_, gamepad)| gamepad.is_some()).map(|(player, _)| player)
+	}
+
+	/// Gamepads that were connected or disconnected since the last frame.
+	/// 
+	/// Each gamepad gets the first free player slot when it is connected, and other gamepads keep their slots when one is disconnected.
+	pub fn gamepad_events(&self) -> &[GamepadEvent] {
+		&self.gamepad_events
+	}
+
+	pub fn is_gamepad_connected(&self, player: usize) -> bool {
+		self.gamepad(player).is_some()
+	}
+
+	/// Name of the gamepad in a player slot, as reported by the driver.
+	pub fn gamepad_name(&self, player: usize) -> Option<&str> {
+		self.gamepad(player).map(|gamepad| gamepad.name.as_str())
+	}
+
+	pub fn is_gamepad_pressed(&self, player: usize, button: GamepadButton) -> bool {
+		self.gamepad(player).is_some_and(|gamepad| gamepad.buttons[button.index()].pressed)
+	}
+
+	pub fn is_gamepad_just_pressed(&self, player: usize, button: GamepadButton) -> bool {
+		self.gamepad(player).is_some_and(|gamepad| gamepad.buttons[button.index()].pressed_this_frame)
+	}
+
+	pub fn is_gamepad_just_released(&self, player: usize, button: GamepadButton) -> bool {
+		self.gamepad(player).is_some_and(|gamepad| gamepad.buttons[button.index()].released_this_frame)
+	}
+
+	/// Position of a stick axis (from -1 to 1) or trigger (from 0 to 1), or 0 if no gamepad is connected in the player slot.
+	pub fn gamepad_axis(&self, player: usize, axis: GamepadAxis) -> f32 {
+		self.gamepad(player).map_or(0., |gamepad| gamepad.axes[axis.index()])
+	}
+
+	fn gamepad(&self, player: usize) -> Option<&Gamepad> {
+		self.gamepads.get(player).and_then(Option::as_ref)
+	}
+
+	/// Queues an event to be processed at the start of the next frame as if it came from the window.
+	/// 
+	/// This is mainly useful for driving input in tests run with [`run_headless`](crate::run_headless).
+	/// Injected events are recorded like real input, so they are ignored during playback of a replay, which already contains them.
+	pub fn inject_event(&self, event: sdl2::event::Event) {
+		self.injected_events.push(event);
+	}
+
+	/// Adds a button to an action, which takes effect from the next frame on.
+	/// 
+	/// A button can only be mapped to one action, so it is removed from any action it was already mapped to.
 	pub fn map_button<Action: 'static>(&self, mapping: ButtonMapping) {
-		self.new_mappings.push((mapping, TypeId::of::<Action>()));
+		self.register_action::<Action>();
+		self.binding_cmds.push(BindingCmd::MapButton(TypeId::of::<Action>(), mapping));
+	}
+
+	/// Adds a source for an axis action, which takes effect from the next frame on.
+	/// 
+	/// An action can have several mappings (such as a gamepad stick and WASD keys), in which case the one with the largest value is used.
+	pub fn map_axis<Action: 'static>(&self, mapping: impl Into<AxisMapping>) {
+		self.register_action::<Action>();
+		self.binding_cmds.push(BindingCmd::MapAxis(TypeId::of::<Action>(), mapping.into()));
+	}
+
+	fn register_action<Action: 'static>(&self) {
+		self.binding_cmds.push(BindingCmd::Register(TypeId::of::<Action>(), short_type_name(std::any::type_name::<Action>()), std::any::type_name::<Action>()));
+	}
+
+	/// Sets the name an action is saved under in [`Bindings`], which takes effect from the next frame on.
+	/// 
+	/// Actions are named after their type without the module path by default.
+	/// If two action types have the same name, the one mapped last is named after its full path instead and a warning is logged, so this should be called before mapping it.
+	/// Names already used by another action are ignored with a warning.
+	pub fn name_action<Action: 'static>(&self, name: impl Into<String>) {
+		self.binding_cmds.push(BindingCmd::Name(TypeId::of::<Action>(), name.into()));
+	}
+
+	/// Removes a button from whichever action it is mapped to, from the next frame on.
+	pub fn unbind_button(&self, mapping: ButtonMapping) {
+		self.binding_cmds.push(BindingCmd::UnbindButton(mapping));
+	}
+
+	/// Removes every button and axis mapping of an action, from the next frame on.
+	pub fn unbind<Action: 'static>(&self) {
+		self.binding_cmds.push(BindingCmd::UnbindAll(TypeId::of::<Action>()));
+	}
+
+	/// Replaces one of the buttons of an action with another in the same position, from the next frame on.
+	/// 
+	/// Like with [`map_button`](InputCtx::map_button), the new button is removed from any action it was already mapped to.
+	/// Nothing happens if `old` isn't mapped to the action.
+	pub fn rebind<Action: 'static>(&self, old: ButtonMapping, new: ButtonMapping) {
+		self.register_action::<Action>();
+		self.binding_cmds.push(BindingCmd::Rebind(TypeId::of::<Action>(), old, new));
+	}
+
+	/// Buttons mapped to an action, in the order they were added.
+	pub fn button_bindings<Action: 'static>(&self) -> &[ButtonMapping] {
+		self.button_mappings.get(&TypeId::of::<Action>()).map_or(&[], Vec::as_slice)
+	}
+
+	/// Axis mappings of an action, in the order they were added.
+	pub fn axis_bindings<Action: 'static>(&self) -> &[AxisMapping] {
+		self.axis_mappings.get(&TypeId::of::<Action>()).map_or(&[], Vec::as_slice)
+	}
+
+	/// Returns the current bindings of every action, such as for saving them after players change their controls.
+	pub fn bindings(&self) -> Bindings {
+		let mut bindings = Bindings::default();
+		for (id, name) in &self.action_names {
+			if let Some(mappings) = self.button_mappings.get(id) {
+				bindings.buttons.insert(name.clone(), mappings.clone());
+			}
+			if let Some(mappings) = self.axis_mappings.get(id) {
+				bindings.axes.insert(name.clone(), mappings.clone());
+			}
+		}
+		bindings
+	}
+
+	/// Replaces the bindings of each action in the set from the next frame on, leaving other actions alone.
+	/// 
+	/// Actions are matched by name, so they need to be mapped or [named](InputCtx::name_action) first.
+	/// Names that don't match any action are ignored.
+	pub fn load_bindings(&self, bindings: Bindings) {
+		self.binding_cmds.push(BindingCmd::Load(bindings));
+	}
+
+	/// Starts listening for the next button to be pressed, which can then be read with [`heard_input`](InputCtx::heard_input).
+	/// 
+	/// This is meant for controls menus where players pick a button for an action.
+	/// Keys pressed with modifiers held are heard as [shortcuts](ButtonMapping::Shortcut), and gamepad buttons are heard for any gamepad.
+	/// Modifier keys on their own are heard when they are released without any other button being pressed.
+	/// The pressed button still triggers any action it is already mapped to.
+	pub fn listen_for_input(&self) {
+		self.listening.set(true);
+	}
+
+	/// Stops listening without waiting for a button to be pressed.
+	pub fn stop_listening(&self) {
+		self.listening.set(false);
+	}
+
+	pub fn is_listening(&self) -> bool {
+		self.listening.get()
+	}
+
+	/// The button that was heard this frame after [`listen_for_input`](InputCtx::listen_for_input) was called.
+	/// 
+	/// Listening stops once a button is heard.
+	pub fn heard_input(&self) -> Option<ButtonMapping> {
+		self.heard_input
+	}
+
+	/// Value of a one dimensional axis action, or the x component of a two dimensional one.
+	pub fn axis<Action: 'static>(&self) -> f32 {
+		self.axis2d::<Action>().x
+	}
+
+	/// Value of a two dimensional axis action, or 0 if the action has no mappings.
+	pub fn axis2d<Action: 'static>(&self) -> Vec2 {
+		let Some(mappings) = self.axis_mappings.get(&TypeId::of::<Action>()) else {
+			return Vec2::ZERO;
+		};
+		mappings.iter()
+			.map(|mapping| mapping.apply(self.raw_axis(&mapping.source)))
+			.max_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+			.unwrap_or(Vec2::ZERO)
+	}
+
+	fn raw_axis(&self, source: &AxisSource) -> Vec2 {
+		let held = |mapping: &ButtonMapping| if self.held.contains(mapping) { 1. } else { 0. };
+		let stick = |player: usize, stick: GamepadStick| {
+			let (x, y) = stick.axes();
+			vec2(self.gamepad_axis(player, x), self.gamepad_axis(player, y))
+		};
+		let furthest = |values: &mut dyn Iterator<Item = Vec2>| {
+			values.max_by(|a, b| a.length_squared().total_cmp(&b.length_squared())).unwrap_or(Vec2::ZERO)
+		};
+		match source {
+			AxisSource::Gamepad(axis) => furthest(&mut self.gamepads().map(|player| vec2(self.gamepad_axis(player, *axis), 0.))),
+			AxisSource::PlayerGamepad(player, axis) => vec2(self.gamepad_axis(*player, *axis), 0.),
+			AxisSource::GamepadStick(s) => furthest(&mut self.gamepads().map(|player| stick(player, *s))),
+			AxisSource::PlayerGamepadStick(player, s) => stick(*player, *s),
+			AxisSource::MouseX => vec2(self.mouse_delta.x, 0.),
+			AxisSource::MouseY => vec2(self.mouse_delta.y, 0.),
+			AxisSource::MouseMotion => self.mouse_delta,
+			AxisSource::MouseWheel => self.wheel_delta,
+			AxisSource::Buttons { negative, positive } => vec2(held(positive) - held(negative), 0.),
+			AxisSource::Buttons2D { up, down, left, right } => {
+				// diagonals would otherwise be faster than straight lines
+				vec2(held(right) - held(left), held(down) - held(up)).clamp_length_max(1.)
+			},
+		}
 	}
 }
\ No newline at end of file
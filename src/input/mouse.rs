@@ -0,0 +1,29 @@
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, serde::Serialize, serde::Deserialize)]
+pub enum MouseButton {
+	Left,
+	Middle,
+	Right,
+	/// First extra button, usually "back".
+	X1,
+	/// Second extra button, usually "forward".
+	X2,
+}
+
+impl MouseButton {
+	pub(crate) fn from_sdl(button: sdl2::mouse::MouseButton) -> Option<Self> {
+		use sdl2::mouse::MouseButton as SdlButton;
+		match button {
+			SdlButton::Left => Some(Self::Left),
+			SdlButton::Middle => Some(Self::Middle),
+			SdlButton::Right => Some(Self::Right),
+			SdlButton::X1 => Some(Self::X1),
+			SdlButton::X2 => Some(Self::X2),
+			SdlButton::Unknown => None,
+		}
+	}
+
+	pub(crate) fn index(self) -> usize {
+		self as usize
+	}
+}
@@ -7,21 +7,119 @@ pub mod input;
 pub mod math;
 pub mod console;
 pub mod gltf;
+pub mod profile;
 
 mod res;
 pub use res::*;
 
+mod config;
+pub use config::*;
+
+mod state;
+mod replay;
+
+mod plugin;
+pub use plugin::*;
+
+mod time;
+pub use time::*;
+
 pub use isopod_derive::*;
 pub use rustc_hash;
+pub use sdl2;
 
 mod util;
 
-use std::{cell::Cell, collections::VecDeque};
+use std::{cell::{Cell, RefCell}, collections::VecDeque};
 
 
 pub trait App {
 	/// Called every time the screen is rendered and provides access to the [EngineCtx].
 	fn update(&mut self, c: &EngineCtx);
+
+	/// Called at a fixed rate (see [`EngineCtx::set_tick_rate`]), before [update](App::update) on frames where a tick is due.
+	/// 
+	/// This may be called several times in one frame to catch up, or not at all if frames are rendered faster than the tick rate.
+	/// Logic that needs to be deterministic, such as physics, should go here and use [`EngineCtx::fixed_dt`] as its timestep.
+	/// Rendering in [update](App::update) can use [`EngineCtx::alpha`] to blend between the last two ticks.
+	fn fixed_update(&mut self, _c: &EngineCtx) {}
+
+	/// Called when the size of the [window canvas](gfx::GfxCtx::window_canvas) changes, with its new size in pixels.
+	fn on_resize(&mut self, _c: &EngineCtx, _new_size: glam::UVec2) {}
+
+	/// Called when the window gains (`true`) or loses (`false`) keyboard focus.
+	fn on_focus_changed(&mut self, _c: &EngineCtx, _focused: bool) {}
+
+	/// Called when the window is minimized.
+	fn on_minimized(&mut self, _c: &EngineCtx) {}
+
+	/// Called when the window is restored after being minimized or maximized.
+	fn on_restored(&mut self, _c: &EngineCtx) {}
+
+	/// Called after [update](App::update) on every frame this app is visible, for drawing that should continue while it is paused.
+	/// 
+	/// Apps that are not used as states can draw in [update](App::update) instead.
+	fn render(&mut self, _c: &EngineCtx) {}
+
+	/// Called when this app is pushed onto the state stack with [`EngineCtx::push_state`] or [`EngineCtx::replace_state`].
+	/// 
+	/// The app returned by the load function gets this call before its first update.
+	fn on_enter(&mut self, _c: &EngineCtx) {}
+
+	/// Called when this app is removed from the state stack with [`EngineCtx::pop_state`] or [`EngineCtx::replace_state`].
+	/// 
+	/// Every app still on the stack (including the one returned by the load function) also gets this call when the application closes, starting from the top.
+	fn on_exit(&mut self, _c: &EngineCtx) {}
+
+	/// Whether the states underneath this one keep getting [update](App::update) and [fixed_update](App::fixed_update) calls while it is on top.
+	/// 
+	/// The default is `false`, so states underneath are paused.
+	fn update_below(&self) -> bool {
+		false
+	}
+
+	/// Whether the states underneath this one keep getting [render](App::render) calls while it is on top, such as for a pause menu drawn over the game.
+	/// 
+	/// The default is `false`.
+	fn render_below(&self) -> bool {
+		false
+	}
+
+	/// Called when the user tries to close the window.
+	/// 
+	/// Every app on the state stack is asked, starting from the top, and the application only closes if none of them return `false`.
+	/// Returning `false` keeps the application running, such as to show a confirmation dialog first.
+	/// The application can still be closed later with [`EngineCtx::quit`].
+	fn on_quit_requested(&mut self, _c: &EngineCtx) -> bool {
+		true
+	}
+}
+
+/// Window events that are passed on to [`App`] callbacks once the frame has started.
+enum LifecycleEvent {
+	Resize,
+	FocusChanged(bool),
+	Minimized,
+	Restored,
+	QuitRequested,
+}
+
+impl LifecycleEvent {
+	fn from_event(event: &sdl2::event::Event) -> Option<Self> {
+		use sdl2::event::{Event, WindowEvent};
+		match event {
+			Event::Quit { .. } => Some(Self::QuitRequested),
+			Event::Window { win_event, .. } => match win_event {
+				WindowEvent::SizeChanged(..) => Some(Self::Resize),
+				WindowEvent::FocusGained => Some(Self::FocusChanged(true)),
+				WindowEvent::FocusLost => Some(Self::FocusChanged(false)),
+				WindowEvent::Minimized => Some(Self::Minimized),
+				WindowEvent::Restored => Some(Self::Restored),
+				_ => None,
+			},
+			_ => None,
+		}
+	}
 }
 
 pub struct EngineCtx {
@@ -30,13 +128,29 @@ pub struct EngineCtx {
 	pub console: console::Console,
 	resources: res::ResourceStorage,
 	/// Time (in seconds) that has elapsed since [update](App::update) was last called.
+	/// 
+	/// This is not affected by the [time scale](Time::set_scale), see [`time`](EngineCtx::time) for scaled time.
 	pub dt: f64,
+	/// Elapsed time, frame count and time scale.
+	pub time: Time,
 	/// Estimation of the current number of frames rendered per second.
 	pub fps: f64,
+	/// How far (from 0 to 1) the current frame is between the last [fixed update](App::fixed_update) and the next one.
+	pub alpha: f64,
+	fixed_dt: Cell<f64>,
+	max_fixed_steps: Cell<u32>,
+	fixed_accumulator: f64,
 	dt_buffer: VecDeque<f64>,
 	dt_buffer_sum: f64,
 	last_fps_update: usize,
+	target_fps: Cell<Option<f64>>,
+	unfocused_fps: Cell<Option<f64>>,
+	smooth_dt: Cell<bool>,
+	focused: bool,
 	should_quit: Cell<bool>,
+	state_transitions: util::BufferCell<state::StateTransition>,
+	queued_plugins: util::BufferCell<Box<dyn Plugin>>,
+	replay: RefCell<replay::Replay>,
 }
 
 impl EngineCtx {
@@ -44,85 +158,661 @@ impl EngineCtx {
 		let gfx = gfx::GfxCtx::new();
 		Self {
 			input: input::InputCtx::new(),
-			console: console::Console::new(&gfx),
+			console: console::Console::new(),
 			resources: res::ResourceStorage::new(),
 			gfx,
 			dt: 0.,
+			time: Time::new(),
 			fps: 1.,
+			alpha: 0.,
+			fixed_dt: Cell::new(1. / 60.),
+			max_fixed_steps: Cell::new(8),
+			fixed_accumulator: 0.,
 			dt_buffer: VecDeque::new(),
 			dt_buffer_sum: 0.,
 			last_fps_update: 0,
+			target_fps: Cell::new(None),
+			unfocused_fps: Cell::new(None),
+			smooth_dt: Cell::new(false),
+			focused: true,
 			should_quit: Cell::new(false),
+			state_transitions: util::BufferCell::new(),
+			queued_plugins: util::BufferCell::new(),
+			replay: RefCell::new(replay::Replay::new()),
 		}
 	}
 
 	/// Reads a resource stored in a [`Res`] smart pointer.
-	pub fn read<'a, T: Resource>(&'a self, res: &'a Res<T>) -> &'a T {
+	/// 
+	/// Any number of resources can be read at once, but the returned guard must be dropped before calling [`write`](EngineCtx::write) or [`insert`](EngineCtx::insert).
+	pub fn read<'a, T: Resource>(&'a self, res: &'a Res<T>) -> ResRef<'a, T> {
 		self.resources.read(res)
 	}
 
-	/// Loads a resource.
+	/// Modifies a resource stored in a [`Res`] smart pointer.
+	/// 
+	/// Changes are visible to every [`Res`] pointing to the same resource.
+	/// Any loads or reloads for the resource are cancelled, so they don't overwrite the changes once they finish.
+	/// 
+	/// # Panics
+	/// Panics if any guard returned by [`read`](EngineCtx::read) or [`write`](EngineCtx::write) is still alive.
+	/// Use [`try_write`](EngineCtx::try_write) to check for that instead.
+	pub fn write<'a, T: Resource>(&'a self, res: &'a Res<T>) -> ResMut<'a, T> {
+		self.resources.write(self, res)
+	}
+
+	/// Like [`write`](EngineCtx::write), but returns `None` instead of panicking if any guard returned by [`read`](EngineCtx::read) or [`write`](EngineCtx::write) is still alive.
+	pub fn try_write<'a, T: Resource>(&'a self, res: &'a Res<T>) -> Option<ResMut<'a, T>> {
+		self.resources.try_write(self, res)
+	}
+
+	/// Stores a resource (such as one that was procedurally generated) under a path.
+	/// 
+	/// Future calls to [`load`](EngineCtx::load) with the same path and type will return it.
+	/// If a resource already exists under the path, its contents are replaced in place and any loads or reloads for it are cancelled.
+	/// 
+	/// # Panics
+	/// Panics if the resource already exists and any guard returned by [`read`](EngineCtx::read) or [`write`](EngineCtx::write) is still alive.
+	pub fn insert<T: Resource>(&self, path: impl Into<String>, value: T) -> Res<T> {
+		self.resources.insert(path, value)
+	}
+
+	/// Loads a resource in the background.
+	/// 
+	/// The path is resolved through the mounts added with [`mount`](EngineCtx::mount).
+	/// The returned [`Res`] holds the [default](Resource::default) for the resource type until loading finishes, at which point it is replaced in place (see [`Res::load_state`]).
+	/// The default is only created once per type and is shared by every resource that is loading or failed to load.
+	/// If loading fails, the error is logged to the [console](EngineCtx::console) and the default is kept.
 	pub fn load<T: Resource>(&self, path: impl Into<String>) -> Res<T> {
 		self.resources.get(self, path)
 	}
 
+	/// Loads a resource immediately, returning the error instead of falling back to the default if loading fails.
+	/// 
+	/// If the resource is already loaded, the existing [`Res`] is returned.
+	/// If it is still loading in the background or failed to load, it is loaded again on this thread and the existing [`Res`] is updated, so the error is always returned if loading fails.
+	/// 
+	/// # Panics
+	/// Panics if the resource already exists without being loaded and any guard returned by [`read`](EngineCtx::read) or [`write`](EngineCtx::write) is still alive.
+	pub fn try_load<T: Resource>(&self, path: impl Into<String>) -> Result<Res<T>, ResourceError> {
+		self.resources.try_get(self, path)
+	}
+
+	/// Reads a RON manifest through the mounted filesystem and adds the resource groups it declares.
+	/// 
+	/// A manifest maps group names to lists of typed resource paths:
+	/// ```ron
+	/// (
+	///     groups: {
+	///         "level1": [
+	///             (type: "texture", path: "textures/grass.png"),
+	///             (type: "gltf", path: "models/level1.gltf"),
+	///         ],
+	///     },
+	/// )
+	/// ```
+	/// The built-in types are `"texture"` ([`GPUTexture2D`](gfx::GPUTexture2D)), `"image"` ([`Texture`](gfx::Texture)), `"shader"` ([`ShaderSource`](gfx::ShaderSource)) and `"gltf"` ([`GltfDecoded`](gltf::GltfDecoded)).
+	/// More can be added with [`register_resource_type`](EngineCtx::register_resource_type).
+	/// Groups replace any existing groups with the same name.
+	pub fn load_manifest(&self, path: &str) -> Result<(), ManifestError> {
+		self.resources.load_manifest(path)
+	}
+
+	/// Allows a resource type to be used in manifests under the given name.
+	/// 
+	/// This must be called before loading any manifest that uses the type.
+	pub fn register_resource_type<T: Resource>(&self, name: impl Into<String>) {
+		self.resources.register_resource_type::<T>(name);
+	}
+
+	/// Starts loading every resource in a group declared in a manifest.
+	/// 
+	/// The resources are kept alive until the group is unloaded with [`unload_group`](EngineCtx::unload_group), even if no other [`Res`] points to them.
+	/// The returned [`LoadGroup`] can be used to track loading progress.
+	/// If the group is already loaded, the existing group is returned.
+	pub fn load_group(&self, name: &str) -> Result<LoadGroup, ManifestError> {
+		self.resources.load_group(self, name)
+	}
+
+	/// Releases the resources kept alive by a group loaded with [`load_group`](EngineCtx::load_group).
+	/// 
+	/// Resources that are still used elsewhere (including by other loaded groups) stay loaded.
+	/// Returns `false` if the group was not loaded.
+	pub fn unload_group(&self, name: &str) -> bool {
+		self.resources.unload_group(name)
+	}
+
+	/// Returns whether a group has been loaded with [`load_group`](EngineCtx::load_group) and not yet unloaded.
+	pub fn is_group_loaded(&self, name: &str) -> bool {
+		self.resources.is_group_loaded(name)
+	}
+
+	/// Lists every resource that is currently alive, sorted by memory size with the largest first.
+	/// 
+	/// Useful for tracking down leaked or redundant resources.
+	/// The same listing can be printed to the console with the `resources` command.
+	/// 
+	/// # Panics
+	/// Panics if any guard returned by [`write`](EngineCtx::write) is still alive.
+	pub fn resources(&self) -> Vec<ResourceInfo> {
+		self.resources.list()
+	}
+
+	/// Adds a source of files that resources can be loaded from.
+	/// 
+	/// When a file exists in multiple mounts, the one with the highest priority is used.
+	/// A typical setup would mount embedded files at the lowest priority, the game's asset directory above that and an optional user mod directory at the highest priority.
+	pub fn mount(&self, priority: i32, mount: impl VfsMount) -> MountID {
+		self.resources.mount(priority, Box::new(mount))
+	}
+
+	/// Removes a mount added with [`mount`](EngineCtx::mount).
+	/// 
+	/// Returns `false` if the mount did not exist.
+	pub fn unmount(&self, id: MountID) -> bool {
+		self.resources.unmount(id)
+	}
+
+	/// Sets whether resources are reloaded when the files they were loaded from change.
+	/// 
+	/// Reloaded resources are replaced in place, so every [`Res`] pointing to them will see the new contents.
+	/// If reloading fails, the error is logged and the old contents are kept.
+	/// This is enabled by default in debug builds.
+	pub fn set_hot_reload(&self, enabled: bool) {
+		self.resources.set_hot_reload(enabled);
+	}
+
+	/// Reads the raw bytes of a file through the mounted filesystem.
+	pub fn read_file(&self, path: &str) -> Result<Vec<u8>, VfsError> {
+		self.resources.read_file(path)
+	}
+
+	/// Durations (in seconds) of recent frames, from oldest to newest.
+	/// 
+	/// These are the measured frame times, even if [dt smoothing](EngineCtx::set_dt_smoothing) is enabled.
+	pub fn frame_times(&self) -> impl ExactSizeIterator<Item = f64> + '_ {
+		self.dt_buffer.iter().copied()
+	}
+
+	/// Limits how many frames are rendered per second, or removes the limit if `None`.
+	/// 
+	/// Frames are paced by sleeping and then spinning for the last moment, so the limit is accurate but uses some CPU.
+	/// This is in addition to any limit imposed by the [present mode](PresentMode).
+	pub fn set_target_fps(&self, fps: Option<f64>) {
+		self.target_fps.set(fps.filter(|fps| *fps > 0.));
+	}
+
+	/// Limits how many frames are rendered per second while the window is not focused, which saves power when the game is in the background.
+	/// 
+	/// If `None`, the [target frame rate](EngineCtx::set_target_fps) is used.
+	pub fn set_unfocused_fps(&self, fps: Option<f64>) {
+		self.unfocused_fps.set(fps.filter(|fps| *fps > 0.));
+	}
+
+	/// Sets whether [`dt`](EngineCtx::dt) is averaged over the last few frames to hide small variations in frame times.
+	/// 
+	/// This makes movement look smoother when frame times jitter, at the cost of reacting more slowly to real changes in frame rate.
+	pub fn set_dt_smoothing(&self, enabled: bool) {
+		self.smooth_dt.set(enabled);
+	}
+
+	/// Returns whether the window currently has keyboard focus.
+	pub fn is_focused(&self) -> bool {
+		self.focused
+	}
+
+	/// Time (in seconds) between calls to [fixed_update](App::fixed_update).
+	pub fn fixed_dt(&self) -> f64 {
+		self.fixed_dt.get()
+	}
+
+	/// Sets how many times per second [fixed_update](App::fixed_update) is called.
+	/// 
+	/// The default is 60.
+	pub fn set_tick_rate(&self, hz: f64) {
+		assert!(hz > 0., "tick rate must be positive");
+		self.fixed_dt.set(1. / hz);
+	}
+
+	/// Sets the maximum number of times [fixed_update](App::fixed_update) can be called in one frame.
+	/// 
+	/// If a frame takes long enough that more ticks are due, the extra ticks are skipped so the game slows down instead of falling further behind.
+	/// The default is 8.
+	pub fn set_max_fixed_steps(&self, steps: u32) {
+		self.max_fixed_steps.set(steps);
+	}
+
+	/// Sets the title of the window.
+	/// 
+	/// Like the other window functions, this takes effect at the start of the next frame.
+	pub fn set_window_title(&self, title: impl Into<String>) {
+		self.gfx.window_cmds.push(gfx::WindowCmd::Title(title.into()));
+	}
+
+	/// Resizes the window (in screen coordinates).
+	/// 
+	/// The [window canvas](gfx::GfxCtx::window_canvas) will have the new size from the next frame on.
+	pub fn set_window_size(&self, width: u32, height: u32) {
+		self.gfx.window_cmds.push(gfx::WindowCmd::Size(glam::UVec2::new(width, height)));
+	}
+
+	/// Switches between windowed, borderless and fullscreen modes.
+	pub fn set_window_mode(&self, mode: WindowMode) {
+		self.gfx.window_cmds.push(gfx::WindowCmd::Mode(mode));
+	}
+
+	/// Shows or hides the mouse cursor while it is over the window.
+	pub fn set_cursor_visible(&self, visible: bool) {
+		self.gfx.window_cmds.push(gfx::WindowCmd::CursorVisible(visible));
+	}
+
+	/// Locks the mouse cursor inside the window.
+	pub fn set_cursor_grab(&self, grab: bool) {
+		self.gfx.window_cmds.push(gfx::WindowCmd::CursorGrab(grab));
+	}
+
+	/// Hides the cursor and keeps reporting [mouse movement](input::InputCtx::mouse_delta) even when the cursor would have hit the edge of the screen.
+	/// 
+	/// This is intended for first person camera controls.
+	pub fn set_relative_mouse_mode(&self, enabled: bool) {
+		self.gfx.window_cmds.push(gfx::WindowCmd::RelativeMouse(enabled));
+	}
+
+	/// Sets the icon shown for the window by the operating system.
+	/// 
+	/// If the texture is 3D, anything past the first layer is ignored.
+	pub fn set_window_icon(&self, icon: gfx::Texture<glam::U8Vec4>) {
+		self.gfx.window_cmds.push(gfx::WindowCmd::Icon(icon));
+	}
+
+	/// Returns every connected monitor.
+	/// 
+	/// This is empty when running with [`run_headless`].
+	pub fn monitors(&self) -> &[gfx::MonitorInfo] {
+		&self.gfx.monitors
+	}
+
+	/// Returns the index in [`monitors`](EngineCtx::monitors) of the monitor the window is currently on.
+	/// 
+	/// This is `None` if that monitor is missing from the list, such as when it couldn't be queried.
+	pub fn window_monitor(&self) -> Option<usize> {
+		self.gfx.window_monitor
+	}
+
+	/// Returns the resource updates and draw commands of the last rendered frame when running with [`run_headless`].
+	/// 
+	/// Frames are rendered after [`App::update`], so during an update this is the previous frame.
+	/// This is `None` when running with a window.
+	pub fn headless_frame(&self) -> Option<&gfx::HeadlessFrame> {
+		self.gfx.headless_frame.as_ref()
+	}
+
+	/// Pushes a state onto the state stack, pausing the states underneath unless it allows otherwise (see [`App::update_below`]).
+	/// 
+	/// The app returned by the load function is always at the bottom of the stack.
+	/// Like the other state functions, this takes effect at the end of the current frame.
+	pub fn push_state(&self, state: impl App + 'static) {
+		self.state_transitions.push(state::StateTransition::Push(Box::new(state)));
+	}
+
+	/// Removes the state on top of the state stack.
+	/// 
+	/// The app returned by the load function can not be removed.
+	pub fn pop_state(&self) {
+		self.state_transitions.push(state::StateTransition::Pop);
+	}
+
+	/// Replaces the state on top of the state stack, or pushes it if the stack only contains the app returned by the load function.
+	pub fn replace_state(&self, state: impl App + 'static) {
+		self.state_transitions.push(state::StateTransition::Replace(Box::new(state)));
+	}
+
+	/// Adds a [`Plugin`], which is built at the start of the next frame (or before the first frame if called from the load function).
+	pub fn add_plugin(&self, plugin: impl Plugin + 'static) {
+		self.queued_plugins.push(Box::new(plugin));
+	}
+
 	/// Quits the application at the end of the current call to [update](App::update).
 	pub fn quit(&self) {
 		self.should_quit.set(true);
 	}
 }
 
+/// Calls [`App::fixed_update`] for every tick that has become due since the last frame.
+fn fixed_update(ctx: &mut EngineCtx, layers: &mut [&mut dyn App]) {
+	ctx.fixed_accumulator += ctx.time.dt();
+	let mut steps = 0;
+	while ctx.fixed_accumulator >= ctx.fixed_dt.get() {
+		if steps >= ctx.max_fixed_steps.get() {
+			ctx.fixed_accumulator %= ctx.fixed_dt.get();
+			break;
+		}
+		for layer in layers.iter_mut() {
+			layer.fixed_update(ctx);
+		}
+		ctx.fixed_accumulator -= ctx.fixed_dt.get();
+		steps += 1;
+	}
+	ctx.alpha = ctx.fixed_accumulator / ctx.fixed_dt.get();
+}
+
+/// Runs a single frame once input has been processed.
+fn frame(
+	ctx: &mut EngineCtx, gfx_sys: &mut gfx::GfxSys, game: &mut dyn App, states: &mut state::StateStack, plugins: &mut plugin::Plugins,
+	events: Vec<LifecycleEvent>,
+) {
+	gfx_sys.start_update(&mut ctx.gfx, true);
+	ctx.input.canvas_scale = gfx_sys.canvas_scale();
+	ctx.input.relative_mouse = gfx_sys.relative_mouse_mode();
+	{
+		profile_scope!("resources");
+		ctx.resources.poll_changes();
+		ctx.resources.finish_loads(ctx);
+		ctx.resources.apply_loads();
+	}
+	plugins.add_queued(ctx);
+	let mut layers = states.layers(game);
+
+	// every state gets window events, starting from the top
+	let mut resized = false;
+	for event in events {
+		match event {
+			LifecycleEvent::Resize => resized = true,
+			LifecycleEvent::FocusChanged(focused) => {
+				ctx.focused = focused;
+				layers.iter_mut().rev().for_each(|layer| layer.on_focus_changed(ctx, focused));
+			},
+			LifecycleEvent::Minimized => layers.iter_mut().rev().for_each(|layer| layer.on_minimized(ctx)),
+			LifecycleEvent::Restored => layers.iter_mut().rev().for_each(|layer| layer.on_restored(ctx)),
+			LifecycleEvent::QuitRequested => {
+				// every layer is asked, even once one has refused
+				let mut quit = true;
+				for layer in layers.iter_mut().rev() {
+					quit &= layer.on_quit_requested(ctx);
+				}
+				if quit {
+					ctx.should_quit.set(true);
+				}
+			},
+		}
+	}
+	// several resizes can happen in one frame but only the final size matters
+	if resized {
+		let size = ctx.gfx.window_canvas.size;
+		layers.iter_mut().rev().for_each(|layer| layer.on_resize(ctx, size));
+	}
+
+	let lowest_updated = state::lowest_layer(&layers, |layer| layer.update_below());
+	let lowest_rendered = state::lowest_layer(&layers, |layer| layer.render_below());
+	{
+		profile_scope!("plugin pre_update");
+		plugins.pre_update(ctx);
+	}
+	{
+		profile_scope!("fixed_update");
+		fixed_update(ctx, &mut layers[lowest_updated..]);
+	}
+	{
+		profile_scope!("update");
+		for layer in &mut layers[lowest_updated..] {
+			layer.update(ctx);
+		}
+	}
+	{
+		profile_scope!("render");
+		for layer in &mut layers[lowest_rendered..] {
+			layer.render(ctx);
+		}
+	}
+	{
+		profile_scope!("plugin post_update");
+		plugins.post_update(ctx);
+	}
+	{
+		profile_scope!("gfx render");
+		gfx_sys.render(&mut ctx.gfx);
+	}
+	states.apply_transitions(ctx);
+}
+
+/// Number of recent frames averaged when [dt smoothing](EngineCtx::set_dt_smoothing) is enabled.
+const SMOOTHED_FRAMES: usize = 8;
+
+/// Time before the end of a frame at which frame pacing switches from sleeping to spinning, since sleeps can overshoot.
+const PACING_SPIN_TIME: std::time::Duration = std::time::Duration::from_micros(1500);
+
+/// Records how long the last frame took.
+fn end_frame(ctx: &mut EngineCtx, dt: f64) {
+	ctx.dt_buffer_sum += dt;
+	ctx.dt_buffer.push_back(dt);
+	if ctx.dt_buffer.len() >= 60 {
+		ctx.dt_buffer_sum -= ctx.dt_buffer.pop_front().unwrap();
+	}
+	ctx.dt = if ctx.smooth_dt.get() {
+		let n = ctx.dt_buffer.len().min(SMOOTHED_FRAMES);
+		ctx.dt_buffer.iter().rev().take(n).sum::<f64>() / n as f64
+	} else {
+		dt
+	};
+	ctx.time.advance(ctx.dt);
+	profile::end_frame();
+	ctx.last_fps_update += 1;
+	if ctx.last_fps_update >= 15 {
+		ctx.fps = 1.0 / (ctx.dt_buffer_sum / ctx.dt_buffer.len() as f64);
+		ctx.last_fps_update = 0;
+	}
+}
+
+/// Waits until the next frame should start according to the target frame rate.
+/// 
+/// `deadline` is when the last frame was due and is updated to when this frame is due.
+fn pace_frame(ctx: &EngineCtx, deadline: &mut std::time::Instant) {
+	let target_fps = if ctx.focused {
+		ctx.target_fps.get()
+	} else {
+		ctx.unfocused_fps.get().or(ctx.target_fps.get())
+	};
+	let now = std::time::Instant::now();
+	let Some(target_fps) = target_fps else {
+		*deadline = now;
+		return;
+	};
+	// frames are scheduled from the previous deadline so that small overshoots don't add up, unless the game has fallen behind
+	*deadline = (*deadline + std::time::Duration::from_secs_f64(1. / target_fps)).max(now);
+	if let Some(sleep_time) = deadline.checked_duration_since(now + PACING_SPIN_TIME) {
+		std::thread::sleep(sleep_time);
+	}
+	while std::time::Instant::now() < *deadline {
+		std::hint::spin_loop();
+	}
+}
+
+/// Starts a frame of input and processes the events injected during the last frame, or the next frame of events during playback of a replay.
+/// 
+/// Returns whether a replay is playing, in which case real input should be ignored.
+fn start_events(
+	ctx: &mut EngineCtx, gfx_sys: &mut gfx::GfxSys, gamepad_sys: &mut input::GamepadSys, plugins: &mut plugin::Plugins,
+	events: &mut Vec<LifecycleEvent>,
+) -> bool {
+	for warning in ctx.input.start_update() {
+		ctx.console.warn(warning);
+	}
+	let injected = ctx.input.take_injected_events();
+	let replay = ctx.replay.get_mut();
+	let playback = replay.next_events();
+	if replay.is_playing() && playback.is_none() {
+		replay.stop();
+		ctx.console.log("replay finished");
+	}
+	let playing = playback.is_some();
+	// injected events are part of the recording, so they are replayed instead of injected again
+	match playback {
+		Some(playback) => for event in playback {
+			match event {
+				replay::ReplayEvent::Sdl(event) => process_event(ctx, gfx_sys, gamepad_sys, plugins, events, event),
+				replay::ReplayEvent::GamepadConnected { instance_id, name } => {
+					ctx.input.connect_gamepad(instance_id, name);
+				},
+			}
+		},
+		None => for event in injected {
+			process_event(ctx, gfx_sys, gamepad_sys, plugins, events, event);
+		},
+	}
+	playing
+}
+
+/// Passes an event from the window (or from a replay or [`inject_event`](input::InputCtx::inject_event)) on to the parts of the engine that handle it.
+fn process_event(
+	ctx: &mut EngineCtx, gfx_sys: &mut gfx::GfxSys, gamepad_sys: &mut input::GamepadSys, plugins: &mut plugin::Plugins,
+	events: &mut Vec<LifecycleEvent>, event: sdl2::event::Event,
+) {
+	use sdl2::event::Event;
+	ctx.replay.get_mut().record_event(&event);
+	plugins.on_event(ctx, &event);
+	if let Some((instance_id, name)) = gamepad_sys.process_event(&mut ctx.input, &event) {
+		ctx.replay.get_mut().record_gamepad_connected(instance_id, &name);
+	}
+	if let Some(event) = LifecycleEvent::from_event(&event) {
+		events.push(event);
+	}
+	match event {
+		Event::Quit { .. } => {},
+		Event::Display { .. } => {
+			gfx_sys.refresh_monitors(&mut ctx.gfx);
+		},
+		event => {
+			ctx.input.process_event(event);
+		},
+	}
+}
+
 /// Starts the engine with a given function that returns an [App].
-pub fn run<F: (FnOnce(&EngineCtx) -> T) + 'static, T: App + 'static>(load_fn: F) {
+/// 
+/// The window is created according to the [`RunConfig`], which can be loaded from a settings file with [`RunConfig::from_file`].
+/// 
+/// Input can be recorded to a file by passing `--record <path>` on the command line, and played back frame by frame with `--replay <path>`.
+/// This is useful for reproducing bugs, and the `record("path")`, `replay("path")` and `stop_replay` console commands do the same while the game is running.
+/// Playback is only exact if recording started with the game and nothing else (such as files being loaded) differs between runs.
+pub fn run<F: (FnOnce(&EngineCtx) -> T) + 'static, T: App + 'static>(config: RunConfig, load_fn: F) {
 
 	let sdl = sdl2::init().unwrap();
 	let sdl_video = sdl.video().unwrap();
 	let mut event_pump = sdl.event_pump().unwrap();
+	let mut gamepad_sys = input::GamepadSys::new(&sdl);
 
-	let mut gfx_sys = gfx::GfxSys::new(&sdl_video);
 	let mut ctx = EngineCtx::new();
+	let mut gfx_sys = gfx::GfxSys::new(&sdl_video, &config, &ctx.console);
+	gfx_sys.refresh_monitors(&mut ctx.gfx);
 
 	// initial load
 	gfx_sys.start_update(&mut ctx.gfx, false);
+	let mut plugins = plugin::Plugins::new();
+	ctx.add_plugin(console::ConsolePlugin::new(&ctx.gfx));
+	plugins.add_queued(&mut ctx);
 	let mut game = load_fn(&ctx);
+	plugins.add_queued(&mut ctx);
+	game.on_enter(&ctx);
 	gfx_sys.render(&mut ctx.gfx);
+	let mut states = state::StateStack::new();
+	states.apply_transitions(&mut ctx);
+
+	ctx.set_target_fps(config.target_fps);
+	ctx.set_unfocused_fps(config.unfocused_fps);
+	ctx.set_dt_smoothing(config.smooth_dt);
+
+	match replay::Replay::from_args(std::env::args()) {
+		Ok(replay) => *ctx.replay.get_mut() = replay,
+		Err(e) => ctx.console.error(format!("failed to start recording or replaying input: {}", e)),
+	}
 
 	let mut frame_time = std::time::Instant::now();
+	let mut frame_deadline = frame_time;
 	while !ctx.should_quit.get() {
 
 		// handle events
-		ctx.input.start_update();
-		for event in event_pump.poll_iter() {
-			use sdl2::event::Event;
-			match event {
-				Event::Quit { .. } => {
-					ctx.should_quit.set(true);
-				},
-				event => {
-					ctx.input.process_event(event);
-				},
+		let mut events = vec![];
+		{
+			profile_scope!("events");
+			let playing = start_events(&mut ctx, &mut gfx_sys, &mut gamepad_sys, &mut plugins, &mut events);
+			for event in event_pump.poll_iter() {
+				use sdl2::event::Event;
+				// during playback, real input is ignored but the window can still be closed
+				if playing && !matches!(event, Event::Quit { .. } | Event::Display { .. }) {
+					continue;
+				}
+				process_event(&mut ctx, &mut gfx_sys, &mut gamepad_sys, &mut plugins, &mut events, event);
 			}
 		}
-		gfx_sys.start_update(&mut ctx.gfx, true);
-		game.update(&ctx);
-		ctx.console.update(&ctx.gfx, &ctx.input);
-		gfx_sys.render(&mut ctx.gfx);
-
+		frame(&mut ctx, &mut gfx_sys, &mut game, &mut states, &mut plugins, events);
 
 		// timing
+		{
+			profile_scope!("pacing");
+			pace_frame(&ctx, &mut frame_deadline);
+		}
 		let new_time = std::time::Instant::now();
-		ctx.dt = new_time.duration_since(frame_time).as_secs_f64();
+		let dt = new_time.duration_since(frame_time).as_secs_f64();
+		let dt = match ctx.replay.get_mut().end_frame(dt) {
+			Ok(dt) => dt,
+			Err(e) => {
+				ctx.replay.get_mut().stop();
+				ctx.console.error(format!("stopped recording input: {}", e));
+				dt
+			},
+		};
+		end_frame(&mut ctx, dt);
 		frame_time = new_time;
+	}
+	states.exit_all(&mut game, &ctx);
+}
+
+/// Time (in seconds) that every frame is treated as taking when running with [`run_headless`].
+pub const HEADLESS_DT: f64 = 1. / 60.;
+
+/// Size of the [window canvas](gfx::GfxCtx::window_canvas) when running with [`run_headless`].
+pub const HEADLESS_WINDOW_SIZE: glam::UVec2 = glam::UVec2::new(800, 800);
+
+/// Runs the engine for a number of frames without creating a window or using the GPU, then returns the [App].
+/// 
+/// Every frame has a [`dt`](EngineCtx::dt) of [`HEADLESS_DT`], so runs are deterministic.
+/// Drawing works as normal, and what each frame would have sent to the GPU can be checked with [`headless_frame`](EngineCtx::headless_frame).
+/// Input can be simulated with [`InputCtx::inject_event`](input::InputCtx::inject_event).
+/// This is intended for running game logic in automated tests on machines without a display or GPU.
+/// Only one engine can run at a time, so runs started on several threads (such as by tests running in parallel) take turns.
+/// This means the load function and app must not start another run, which would wait forever.
+/// Stops early if [`quit`](EngineCtx::quit) is called.
+pub fn run_headless<F: FnOnce(&EngineCtx) -> T, T: App>(frames: usize, load_fn: F) -> T {
+	let mut gfx_sys = gfx::GfxSys::new_headless(HEADLESS_WINDOW_SIZE);
+	let mut gamepad_sys = input::GamepadSys::headless();
+	let mut ctx = EngineCtx::new();
 
-		ctx.dt_buffer_sum += ctx.dt;
-		ctx.dt_buffer.push_back(ctx.dt);
-		if ctx.dt_buffer.len() >= 60 {
-			ctx.dt_buffer_sum -= ctx.dt_buffer.pop_front().unwrap();
+	gfx_sys.start_update(&mut ctx.gfx, false);
+	let mut plugins = plugin::Plugins::new();
+	ctx.add_plugin(console::ConsolePlugin::new(&ctx.gfx));
+	plugins.add_queued(&mut ctx);
+	let mut game = load_fn(&ctx);
+	plugins.add_queued(&mut ctx);
+	game.on_enter(&ctx);
+	gfx_sys.render(&mut ctx.gfx);
+	let mut states = state::StateStack::new();
+	states.apply_transitions(&mut ctx);
+
+	ctx.dt = HEADLESS_DT;
+	ctx.time.set_first_dt(HEADLESS_DT);
+	for _ in 0..frames {
+		if ctx.should_quit.get() {
+			break;
 		}
-		ctx.last_fps_update += 1;
-		if ctx.last_fps_update >= 15 {
-			ctx.fps = 1.0 / (ctx.dt_buffer_sum / ctx.dt_buffer.len() as f64);
-			ctx.last_fps_update = 0;
+		let mut events = vec![];
+		start_events(&mut ctx, &mut gfx_sys, &mut gamepad_sys, &mut plugins, &mut events);
+		frame(&mut ctx, &mut gfx_sys, &mut game, &mut states, &mut plugins, events);
+		if let Err(e) = ctx.replay.get_mut().end_frame(HEADLESS_DT) {
+			ctx.replay.get_mut().stop();
+			ctx.console.error(format!("stopped recording input: {}", e));
 		}
+		end_frame(&mut ctx, HEADLESS_DT);
 	}
-}
\ No newline at end of file
+	states.exit_all(&mut game, &ctx);
+	game
+}
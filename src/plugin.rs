@@ -0,0 +1,59 @@
+use crate::EngineCtx;
+
+/// Extension to the engine that runs alongside the [`App`](crate::App), such as debug tools, audio or networking.
+/// 
+/// Plugins are added with [`EngineCtx::add_plugin`] and called in the order they were added.
+/// The [console](EngineCtx::console) is itself a plugin, which is always added first.
+pub trait Plugin {
+	/// Called once when the plugin is added, before any of its other hooks.
+	fn build(&mut self, _c: &EngineCtx) {}
+
+	/// Called every frame before the app's [update](crate::App::update) and [fixed_update](crate::App::fixed_update).
+	fn pre_update(&mut self, _c: &EngineCtx) {}
+
+	/// Called every frame after the app's [render](crate::App::render), before the frame is drawn.
+	fn post_update(&mut self, _c: &EngineCtx) {}
+
+	/// Called for every event received from the window, before it is passed to [input](EngineCtx::input).
+	///
+	/// This includes events [injected](crate::input::InputCtx::inject_event) by the game and events played back from a replay.
+	fn on_event(&mut self, _c: &EngineCtx, _event: &sdl2::event::Event) {}
+}
+
+/// Plugins that have been built, in the order they were added.
+#[derive(Default)]
+pub(crate) struct Plugins {
+	plugins: Vec<Box<dyn Plugin>>,
+}
+
+impl Plugins {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Builds plugins queued with [`EngineCtx::add_plugin`], including any queued by the plugins being built.
+	pub fn add_queued(&mut self, ctx: &mut EngineCtx) {
+		loop {
+			let queued = std::mem::take(ctx.queued_plugins.get_mut());
+			if queued.is_empty() {
+				break;
+			}
+			for mut plugin in queued {
+				plugin.build(ctx);
+				self.plugins.push(plugin);
+			}
+		}
+	}
+
+	pub fn pre_update(&mut self, ctx: &EngineCtx) {
+		self.plugins.iter_mut().for_each(|plugin| plugin.pre_update(ctx));
+	}
+
+	pub fn post_update(&mut self, ctx: &EngineCtx) {
+		self.plugins.iter_mut().for_each(|plugin| plugin.post_update(ctx));
+	}
+
+	pub fn on_event(&mut self, ctx: &EngineCtx, event: &sdl2::event::Event) {
+		self.plugins.iter_mut().for_each(|plugin| plugin.on_event(ctx, event));
+	}
+}
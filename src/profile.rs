@@ -0,0 +1,228 @@
+//! Lightweight CPU profiling with nested scopes.
+//!
+//! Scopes are timed with [`profile_scope!`](crate::profile_scope) and grouped into frames, the most recent of which are kept in a ring buffer.
+//! The engine profiles its own phases (such as event handling, updates and rendering) in the same way.
+//! The last frame can be shown over the game with the `profiler` console command.
+//! Recent frames can be exported with [`export_chrome_trace`] or the `export_trace("path")` console command.
+//!
+//! Profiling is per thread, and only scopes on the main thread are included in frames.
+
+use std::{cell::RefCell, collections::VecDeque, time::Instant};
+
+/// Number of frames kept in the ring buffer.
+pub const PROFILED_FRAMES: usize = 300;
+
+/// A timed scope within a frame.
+#[derive(Clone, Debug)]
+pub struct ProfileSpan {
+	pub name: &'static str,
+	/// How many scopes this one is nested inside of.
+	pub depth: u32,
+	/// Time (in seconds) since the start of the frame.
+	pub start: f64,
+	/// Time (in seconds) spent in the scope.
+	pub duration: f64,
+}
+
+/// Every scope recorded during a frame.
+#[derive(Clone, Debug)]
+pub struct ProfileFrame {
+	/// Time (in seconds) since profiling started.
+	pub start: f64,
+	/// Time (in seconds) from the start of this frame to the start of the next.
+	pub duration: f64,
+	/// Scopes sorted by when they started, so parents come before their children.
+	pub spans: Vec<ProfileSpan>,
+}
+
+struct Profiler {
+	epoch: Instant,
+	frame_start: Instant,
+	depth: u32,
+	spans: Vec<ProfileSpan>,
+	frames: VecDeque<ProfileFrame>,
+}
+
+impl Profiler {
+	fn new() -> Self {
+		let now = Instant::now();
+		Self { epoch: now, frame_start: now, depth: 0, spans: vec![], frames: VecDeque::new() }
+	}
+}
+
+thread_local! {
+	static PROFILER: RefCell<Profiler> = RefCell::new(Profiler::new());
+}
+
+/// Times a scope until it is dropped, usually created with [`profile_scope!`](crate::profile_scope).
+pub struct ProfileScope {
+	name: &'static str,
+	start: Instant,
+}
+
+impl ProfileScope {
+	pub fn new(name: &'static str) -> Self {
+		PROFILER.with_borrow_mut(|profiler| profiler.depth += 1);
+		Self { name, start: Instant::now() }
+	}
+}
+
+impl Drop for ProfileScope {
+	fn drop(&mut self) {
+		let end = Instant::now();
+		PROFILER.with_borrow_mut(|profiler| {
+			profiler.depth -= 1;
+			let span = ProfileSpan {
+				name: self.name,
+				depth: profiler.depth,
+				// scopes that started before the frame did are cut off at the start of the frame
+				start: self.start.saturating_duration_since(profiler.frame_start).as_secs_f64(),
+				duration: (end - self.start.max(profiler.frame_start)).as_secs_f64(),
+			};
+			profiler.spans.push(span);
+		});
+	}
+}
+
+/// Times the rest of the enclosing block under the given name.
+///
+/// ```
+/// # fn update_ai() {}
+/// # fn update_physics() {}
+/// {
+///     isopod::profile_scope!("ai");
+///     update_ai();
+/// }
+/// isopod::profile_scope!("physics");
+/// update_physics();
+/// ```
+#[macro_export]
+macro_rules! profile_scope {
+	($name:expr) => {
+		let _profile_scope = $crate::profile::ProfileScope::new($name);
+	};
+}
+
+/// Moves the scopes recorded since the last call into the ring buffer.
+pub(crate) fn end_frame() {
+	let now = Instant::now();
+	PROFILER.with_borrow_mut(|profiler| {
+		let mut spans = std::mem::take(&mut profiler.spans);
+		spans.sort_by(|a, b| a.start.total_cmp(&b.start).then(a.depth.cmp(&b.depth)));
+		let frame = ProfileFrame {
+			start: (profiler.frame_start - profiler.epoch).as_secs_f64(),
+			duration: (now - profiler.frame_start).as_secs_f64(),
+			spans,
+		};
+		if profiler.frames.len() >= PROFILED_FRAMES {
+			profiler.frames.pop_front();
+		}
+		profiler.frames.push_back(frame);
+		profiler.frame_start = now;
+	});
+}
+
+/// Returns the last completed frame.
+pub fn last_frame() -> Option<ProfileFrame> {
+	PROFILER.with_borrow(|profiler| profiler.frames.back().cloned())
+}
+
+/// Returns recently completed frames, from oldest to newest.
+pub fn frames() -> Vec<ProfileFrame> {
+	PROFILER.with_borrow(|profiler| profiler.frames.iter().cloned().collect())
+}
+
+#[derive(serde::Serialize)]
+struct TraceEvent {
+	name: &'static str,
+	ph: &'static str,
+	/// Start time in microseconds.
+	ts: f64,
+	/// Duration in microseconds.
+	dur: f64,
+	pid: u32,
+	tid: u32,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Trace {
+	trace_events: Vec<TraceEvent>,
+}
+
+/// Writes recent frames to a file in the Chrome trace format, which can be opened in `chrome://tracing` or Perfetto.
+pub fn export_chrome_trace(path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+	let mut trace_events = vec![];
+	for frame in frames() {
+		trace_events.push(TraceEvent { name: "frame", ph: "X", ts: frame.start * 1e6, dur: frame.duration * 1e6, pid: 0, tid: 0 });
+		trace_events.extend(frame.spans.iter().map(|span| TraceEvent {
+			name: span.name, ph: "X", ts: (frame.start + span.start) * 1e6, dur: span.duration * 1e6, pid: 0, tid: 0,
+		}));
+	}
+	let writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+	serde_json::to_writer(writer, &Trace { trace_events })?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn nested_scopes() {
+		end_frame();
+		{
+			profile_scope!("outer");
+			{
+				profile_scope!("inner");
+			}
+			profile_scope!("second");
+		}
+		end_frame();
+		let frame = last_frame().unwrap();
+		let spans = frame.spans.iter().map(|span| (span.name, span.depth)).collect::<Vec<_>>();
+		assert_eq!(spans, [("outer", 0), ("inner", 1), ("second", 1)]);
+		let outer = &frame.spans[0];
+		for child in &frame.spans[1..] {
+			assert!(child.start >= outer.start);
+			assert!(child.start + child.duration <= outer.start + outer.duration);
+		}
+		assert!(frame.spans[1].start <= frame.spans[2].start);
+	}
+
+	#[test]
+	fn ring_buffer() {
+		{
+			profile_scope!("oldest");
+		}
+		for _ in 0..PROFILED_FRAMES {
+			end_frame();
+		}
+		assert_eq!(frames()[0].spans[0].name, "oldest");
+		end_frame();
+		let frames = frames();
+		assert_eq!(frames.len(), PROFILED_FRAMES);
+		assert!(frames.iter().all(|frame| frame.spans.is_empty()));
+		assert!(frames.windows(2).all(|pair| pair[0].start <= pair[1].start));
+	}
+
+	#[test]
+	fn chrome_trace() {
+		{
+			profile_scope!("update");
+		}
+		end_frame();
+		let path = std::env::temp_dir().join(format!("isopod_trace_test_{}.json", std::process::id()));
+		export_chrome_trace(&path).unwrap();
+		let trace: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+		std::fs::remove_file(&path).unwrap();
+		let events = trace["traceEvents"].as_array().unwrap();
+		let names = events.iter().map(|event| event["name"].as_str().unwrap()).collect::<Vec<_>>();
+		assert_eq!(names, ["frame", "update"]);
+		for event in events {
+			assert_eq!(event["ph"], "X");
+			assert!(event["ts"].as_f64().unwrap() >= 0.);
+			assert!(event["dur"].as_f64().unwrap() >= 0.);
+		}
+	}
+}
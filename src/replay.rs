@@ -0,0 +1,326 @@
+use std::{fs::File, io::{BufRead, BufReader, BufWriter, Write}, path::Path};
+
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::{Keycode, Mod, Scancode};
+use sdl2::mouse::{MouseButton, MouseState, MouseWheelDirection};
+use sdl2::controller::{Axis, Button};
+
+// Replay files are JSON lines, one `RecordedFrame` per line, so a recording is still usable if the game crashes.
+
+/// An SDL event in a form that can be saved.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RecordedEvent {
+	Quit,
+	FocusGained,
+	FocusLost,
+	Minimized,
+	Restored,
+	SizeChanged(i32, i32),
+	KeyDown { keycode: Option<i32>, scancode: Option<i32>, keymod: u16, repeat: bool },
+	KeyUp { keycode: Option<i32>, scancode: Option<i32>, keymod: u16, repeat: bool },
+	TextInput(String),
+	MouseMotion { state: u32, x: i32, y: i32, xrel: i32, yrel: i32 },
+	MouseButtonDown { button: u8, clicks: u8, x: i32, y: i32 },
+	MouseButtonUp { button: u8, clicks: u8, x: i32, y: i32 },
+	MouseWheel { x: i32, y: i32, flipped: bool, precise_x: f32, precise_y: f32, mouse_x: i32, mouse_y: i32 },
+	// controller buttons and axes are saved by name, since sdl2 doesn't expose their numbers
+	ControllerButtonDown { which: u32, button: String },
+	ControllerButtonUp { which: u32, button: String },
+	ControllerAxisMotion { which: u32, axis: String, value: i16 },
+	ControllerDeviceRemoved { which: u32 },
+	/// A gamepad being opened, saved instead of `ControllerDeviceAdded` since that refers to a device that may not exist during playback.
+	GamepadConnected { which: u32, name: String },
+}
+
+impl RecordedEvent {
+	/// Returns `None` for events that don't affect the game.
+	fn from_event(event: &Event) -> Option<Self> {
+		Some(match event {
+			Event::Quit { .. } => Self::Quit,
+			Event::Window { win_event, .. } => match win_event {
+				WindowEvent::FocusGained => Self::FocusGained,
+				WindowEvent::FocusLost => Self::FocusLost,
+				WindowEvent::Minimized => Self::Minimized,
+				WindowEvent::Restored => Self::Restored,
+				WindowEvent::SizeChanged(w, h) => Self::SizeChanged(*w, *h),
+				_ => return None,
+			},
+			Event::KeyDown { keycode, scancode, keymod, repeat, .. } => Self::KeyDown {
+				keycode: keycode.map(|k| k.into_i32()), scancode: scancode.map(|s| s as i32), keymod: keymod.bits(), repeat: *repeat,
+			},
+			Event::KeyUp { keycode, scancode, keymod, repeat, .. } => Self::KeyUp {
+				keycode: keycode.map(|k| k.into_i32()), scancode: scancode.map(|s| s as i32), keymod: keymod.bits(), repeat: *repeat,
+			},
+			Event::TextInput { text, .. } => Self::TextInput(text.clone()),
+			Event::MouseMotion { mousestate, x, y, xrel, yrel, .. } => Self::MouseMotion {
+				state: mousestate.to_sdl_state(), x: *x, y: *y, xrel: *xrel, yrel: *yrel,
+			},
+			Event::MouseButtonDown { mouse_btn, clicks, x, y, .. } => Self::MouseButtonDown { button: *mouse_btn as u8, clicks: *clicks, x: *x, y: *y },
+			Event::MouseButtonUp { mouse_btn, clicks, x, y, .. } => Self::MouseButtonUp { button: *mouse_btn as u8, clicks: *clicks, x: *x, y: *y },
+			Event::MouseWheel { x, y, direction, precise_x, precise_y, mouse_x, mouse_y, .. } => Self::MouseWheel {
+				x: *x, y: *y, flipped: *direction == MouseWheelDirection::Flipped,
+				precise_x: *precise_x, precise_y: *precise_y, mouse_x: *mouse_x, mouse_y: *mouse_y,
+			},
+			Event::ControllerButtonDown { which, button, .. } => Self::ControllerButtonDown { which: *which, button: button.string() },
+			Event::ControllerButtonUp { which, button, .. } => Self::ControllerButtonUp { which: *which, button: button.string() },
+			Event::ControllerAxisMotion { which, axis, value, .. } => Self::ControllerAxisMotion { which: *which, axis: axis.string(), value: *value },
+			Event::ControllerDeviceRemoved { which, .. } => Self::ControllerDeviceRemoved { which: *which },
+			_ => return None,
+		})
+	}
+
+	/// Returns `None` for controller buttons and axes that aren't known to this version of SDL.
+	fn into_event(self) -> Option<ReplayEvent> {
+		let window = |win_event| Event::Window { timestamp: 0, window_id: 0, win_event };
+		Some(ReplayEvent::Sdl(match self {
+			Self::Quit => Event::Quit { timestamp: 0 },
+			Self::FocusGained => window(WindowEvent::FocusGained),
+			Self::FocusLost => window(WindowEvent::FocusLost),
+			Self::Minimized => window(WindowEvent::Minimized),
+			Self::Restored => window(WindowEvent::Restored),
+			Self::SizeChanged(w, h) => window(WindowEvent::SizeChanged(w, h)),
+			Self::KeyDown { keycode, scancode, keymod, repeat } => Event::KeyDown {
+				timestamp: 0, window_id: 0,
+				keycode: keycode.and_then(Keycode::from_i32), scancode: scancode.and_then(Scancode::from_i32),
+				keymod: Mod::from_bits_truncate(keymod), repeat,
+			},
+			Self::KeyUp { keycode, scancode, keymod, repeat } => Event::KeyUp {
+				timestamp: 0, window_id: 0,
+				keycode: keycode.and_then(Keycode::from_i32), scancode: scancode.and_then(Scancode::from_i32),
+				keymod: Mod::from_bits_truncate(keymod), repeat,
+			},
+			Self::TextInput(text) => Event::TextInput { timestamp: 0, window_id: 0, text },
+			Self::MouseMotion { state, x, y, xrel, yrel } => Event::MouseMotion {
+				timestamp: 0, window_id: 0, which: 0, mousestate: MouseState::from_sdl_state(state), x, y, xrel, yrel,
+			},
+			Self::MouseButtonDown { button, clicks, x, y } => Event::MouseButtonDown {
+				timestamp: 0, window_id: 0, which: 0, mouse_btn: MouseButton::from_ll(button), clicks, x, y,
+			},
+			Self::MouseButtonUp { button, clicks, x, y } => Event::MouseButtonUp {
+				timestamp: 0, window_id: 0, which: 0, mouse_btn: MouseButton::from_ll(button), clicks, x, y,
+			},
+			Self::MouseWheel { x, y, flipped, precise_x, precise_y, mouse_x, mouse_y } => Event::MouseWheel {
+				timestamp: 0, window_id: 0, which: 0, x, y,
+				direction: if flipped { MouseWheelDirection::Flipped } else { MouseWheelDirection::Normal },
+				precise_x, precise_y, mouse_x, mouse_y,
+			},
+			Self::ControllerButtonDown { which, button } => Event::ControllerButtonDown { timestamp: 0, which, button: Button::from_string(&button)? },
+			Self::ControllerButtonUp { which, button } => Event::ControllerButtonUp { timestamp: 0, which, button: Button::from_string(&button)? },
+			Self::ControllerAxisMotion { which, axis, value } => Event::ControllerAxisMotion { timestamp: 0, which, axis: Axis::from_string(&axis)?, value },
+			Self::ControllerDeviceRemoved { which } => Event::ControllerDeviceRemoved { timestamp: 0, which },
+			Self::GamepadConnected { which, name } => return Some(ReplayEvent::GamepadConnected { instance_id: which, name }),
+		}))
+	}
+}
+
+/// An event played back from a replay.
+pub(crate) enum ReplayEvent {
+	Sdl(Event),
+	/// A gamepad that was connected during recording, which should take the same player slot without a real device.
+	GamepadConnected { instance_id: u32, name: String },
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct RecordedFrame {
+	events: Vec<RecordedEvent>,
+	/// Duration of the frame, which becomes the `dt` of the next frame.
+	dt: f64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum ReplayError {
+	#[error("io error")]
+	IoError(#[from] std::io::Error),
+	#[error("invalid replay file")]
+	ParseError(#[from] serde_json::Error),
+	#[error("missing path after {0}")]
+	MissingPath(String),
+}
+
+enum ReplayMode {
+	Idle,
+	Recording {
+		writer: BufWriter<File>,
+		events: Vec<RecordedEvent>,
+	},
+	Playing(std::iter::Peekable<std::vec::IntoIter<RecordedFrame>>),
+}
+
+/// Records the events and frame times of a session, or plays them back in place of real input.
+pub(crate) struct Replay {
+	mode: ReplayMode,
+}
+
+impl Replay {
+	pub fn new() -> Self {
+		Self { mode: ReplayMode::Idle }
+	}
+
+	/// Starts recording or playback if requested with `--record <path>` or `--replay <path>`.
+	pub fn from_args(args: impl Iterator<Item = String>) -> Result<Self, ReplayError> {
+		let mut replay = Self::new();
+		let mut args = args.skip(1);
+		while let Some(arg) = args.next() {
+			match arg.as_str() {
+				"--record" => replay.start_recording(args.next().ok_or(ReplayError::MissingPath(arg))?)?,
+				"--replay" => replay.start_playback(args.next().ok_or(ReplayError::MissingPath(arg))?)?,
+				_ => {},
+			}
+		}
+		Ok(replay)
+	}
+
+	pub fn start_recording(&mut self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+		let writer = BufWriter::new(File::create(path)?);
+		self.mode = ReplayMode::Recording { writer, events: vec![] };
+		Ok(())
+	}
+
+	pub fn start_playback(&mut self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+		let mut frames = vec![];
+		for line in BufReader::new(File::open(path)?).lines() {
+			let line = line?;
+			if !line.is_empty() {
+				frames.push(serde_json::from_str(&line)?);
+			}
+		}
+		self.mode = ReplayMode::Playing(frames.into_iter().peekable());
+		Ok(())
+	}
+
+	pub fn stop(&mut self) {
+		self.mode = ReplayMode::Idle;
+	}
+
+	pub fn is_recording(&self) -> bool {
+		matches!(self.mode, ReplayMode::Recording { .. })
+	}
+
+	pub fn is_playing(&self) -> bool {
+		matches!(self.mode, ReplayMode::Playing(_))
+	}
+
+	/// Saves an event if recording.
+	pub fn record_event(&mut self, event: &Event) {
+		if let ReplayMode::Recording { events, .. } = &mut self.mode {
+			events.extend(RecordedEvent::from_event(event));
+		}
+	}
+
+	/// Saves a gamepad being connected if recording, so that it gets the same player slot during playback.
+	pub fn record_gamepad_connected(&mut self, instance_id: u32, name: &str) {
+		if let ReplayMode::Recording { events, .. } = &mut self.mode {
+			events.push(RecordedEvent::GamepadConnected { which: instance_id, name: name.to_string() });
+		}
+	}
+
+	/// Returns the events for the next frame of playback, or `None` once playback has finished.
+	pub fn next_events(&mut self) -> Option<Vec<ReplayEvent>> {
+		let ReplayMode::Playing(frames) = &mut self.mode else {
+			return None;
+		};
+		// the frame itself is consumed by `end_frame`, which needs its dt
+		let events = std::mem::take(&mut frames.peek_mut()?.events);
+		Some(events.into_iter().filter_map(RecordedEvent::into_event).collect())
+	}
+
+	/// Finishes a frame, returning the frame time to use in place of the measured `dt` during playback.
+	pub fn end_frame(&mut self, dt: f64) -> Result<f64, ReplayError> {
+		match &mut self.mode {
+			ReplayMode::Idle => Ok(dt),
+			ReplayMode::Recording { writer, events } => {
+				let frame = RecordedFrame { events: std::mem::take(events), dt };
+				serde_json::to_writer(&mut *writer, &frame)?;
+				writer.write_all(b"\n")?;
+				writer.flush()?;
+				Ok(dt)
+			},
+			ReplayMode::Playing(frames) => Ok(frames.next().map_or(dt, |frame| frame.dt)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use sdl2::event::Event;
+	use sdl2::keyboard::{Mod, Scancode};
+
+	use crate::{input::{ButtonMapping, Key}, App, EngineCtx};
+
+	struct Jump;
+
+	/// Presses and releases space by injecting events when `inject` is set, and logs whether it is held every frame.
+	struct Jumper {
+		inject: bool,
+		frames: usize,
+		held: Vec<bool>,
+	}
+
+	impl App for Jumper {
+		fn update(&mut self, c: &EngineCtx) {
+			self.frames += 1;
+			self.held.push(c.input.is_pressed::<Jump>());
+			let scancode = Some(Scancode::Space);
+			match self.frames {
+				2 if self.inject => c.input.inject_event(Event::KeyDown { timestamp: 0, window_id: 0, keycode: None, scancode, keymod: Mod::NOMOD, repeat: false }),
+				4 if self.inject => c.input.inject_event(Event::KeyUp { timestamp: 0, window_id: 0, keycode: None, scancode, keymod: Mod::NOMOD, repeat: false }),
+				_ => {},
+			}
+		}
+	}
+
+	fn run(inject: bool, start: impl FnOnce(&mut super::Replay)) -> Vec<bool> {
+		crate::run_headless(6, |c| {
+			start(&mut c.replay.borrow_mut());
+			c.input.map_button::<Jump>(ButtonMapping::LogicalKey(Key::SPACE));
+			Jumper { inject, frames: 0, held: vec![] }
+		}).held
+	}
+
+	#[test]
+	fn injected_events_round_trip() {
+		let path = std::env::temp_dir().join(format!("isopod_replay_test_{}.jsonl", std::process::id()));
+		let recorded = run(true, |replay| replay.start_recording(&path).unwrap());
+		assert_eq!(recorded, [false, false, true, true, false, false]);
+		// the replay brings back the injected events, which aren't injected again
+		let replayed = run(false, |replay| replay.start_playback(&path).unwrap());
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(replayed, recorded);
+	}
+
+	/// Logs the player slot and name of every connected gamepad.
+	#[derive(Default)]
+	struct PadLog {
+		frames: Vec<Vec<(usize, String)>>,
+	}
+
+	impl App for PadLog {
+		fn update(&mut self, c: &EngineCtx) {
+			let slots = c.input.gamepads().map(|player| (player, c.input.gamepad_name(player).unwrap().to_string()));
+			self.frames.push(slots.collect());
+		}
+	}
+
+	#[test]
+	fn gamepad_connections_round_trip() {
+		let path = std::env::temp_dir().join(format!("isopod_replay_gamepads_{}.jsonl", std::process::id()));
+		let mut replay = super::Replay::new();
+		replay.start_recording(&path).unwrap();
+		replay.record_gamepad_connected(7, "Pad B");
+		replay.record_gamepad_connected(3, "Pad A");
+		replay.end_frame(crate::HEADLESS_DT).unwrap();
+		replay.record_event(&Event::ControllerDeviceRemoved { timestamp: 0, which: 7 });
+		replay.end_frame(crate::HEADLESS_DT).unwrap();
+		replay.stop();
+
+		let game = crate::run_headless(2, |c| {
+			c.replay.borrow_mut().start_playback(&path).unwrap();
+			PadLog::default()
+		});
+		std::fs::remove_file(&path).unwrap();
+		// the second gamepad keeps its slot when the first one is disconnected
+		let pad = |player, name: &str| (player, name.to_string());
+		assert_eq!(game.frames, [vec![pad(0, "Pad B"), pad(1, "Pad A")], vec![pad(1, "Pad A")]]);
+	}
+}
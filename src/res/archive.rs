@@ -0,0 +1,424 @@
+use std::{io::{ErrorKind, Read, Seek, SeekFrom, Write}, path::{Path, PathBuf}, sync::Mutex};
+
+use rustc_hash::FxHashMap;
+
+use super::{Vfs, VfsMount};
+
+// Archive layout (all integers are little endian):
+//
+// magic: [u8; 8]
+// version: u32
+// entry count: u32
+// entries: [
+//   path length: u32
+//   path: [u8; path length] (utf-8, normalized)
+//   compression: u8
+//   offset: u64 (relative to the end of the index)
+//   stored size: u64
+//   size: u64
+// ]
+// blobs: [u8]
+
+const MAGIC: [u8; 8] = *b"ISOPODAR";
+const VERSION: u32 = 1;
+const DEFLATE_LEVEL: u8 = 6;
+
+/// How files are stored in an archive.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ArchiveCompression {
+	None,
+	/// zlib compression, only used when it actually makes the file smaller.
+	#[default]
+	Deflate,
+}
+
+impl ArchiveCompression {
+	fn from_u8(v: u8) -> Option<Self> {
+		match v {
+			0 => Some(Self::None),
+			1 => Some(Self::Deflate),
+			_ => None,
+		}
+	}
+
+	fn to_u8(self) -> u8 {
+		match self {
+			Self::None => 0,
+			Self::Deflate => 1,
+		}
+	}
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveError {
+	#[error("failed to access \"{}\"", path.display())]
+	IoError {
+		path: PathBuf,
+		#[source]
+		source: std::io::Error,
+	},
+	#[error("failed to write archive")]
+	WriteError(#[source] std::io::Error),
+	#[error("not an archive")]
+	InvalidMagic,
+	#[error("unsupported archive version {0}")]
+	UnsupportedVersion(u32),
+	#[error("invalid path \"{0}\"")]
+	InvalidPath(String),
+	#[error("archive is corrupt")]
+	Corrupt,
+}
+
+struct BuilderEntry {
+	path: String,
+	compression: ArchiveCompression,
+	size: u64,
+	data: Vec<u8>,
+}
+
+/// Creates archives that can be mounted with [`ArchiveMount`].
+///
+/// Shipping a single archive is faster to install than many loose files and avoids casual tampering.
+#[derive(Default)]
+pub struct ArchiveBuilder {
+	entries: FxHashMap<String, BuilderEntry>,
+	compression: ArchiveCompression,
+}
+
+impl ArchiveBuilder {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets the compression used for files added after this call.
+	pub fn compression(mut self, compression: ArchiveCompression) -> Self {
+		self.compression = compression;
+		self
+	}
+
+	/// Adds a file to the archive, replacing any existing file with the same path.
+	pub fn add_file(&mut self, path: &str, bytes: &[u8]) -> Result<(), ArchiveError> {
+		let path = Vfs::normalize_path(path).ok_or_else(|| ArchiveError::InvalidPath(path.to_owned()))?;
+		let (compression, data) = match self.compression {
+			ArchiveCompression::None => (ArchiveCompression::None, bytes.to_vec()),
+			ArchiveCompression::Deflate => {
+				let compressed = miniz_oxide::deflate::compress_to_vec_zlib(bytes, DEFLATE_LEVEL);
+				if compressed.len() < bytes.len() {
+					(ArchiveCompression::Deflate, compressed)
+				} else {
+					(ArchiveCompression::None, bytes.to_vec())
+				}
+			},
+		};
+		self.entries.insert(path.clone(), BuilderEntry { path, compression, size: bytes.len() as u64, data });
+		Ok(())
+	}
+
+	/// Adds every file in a directory (recursively), using paths relative to that directory.
+	pub fn add_dir(&mut self, root: impl AsRef<Path>) -> Result<(), ArchiveError> {
+		let root = root.as_ref();
+		let mut dirs = vec![root.to_path_buf()];
+		while let Some(dir) = dirs.pop() {
+			let io_error = |source| ArchiveError::IoError { path: dir.clone(), source };
+			for entry in std::fs::read_dir(&dir).map_err(io_error)? {
+				let path = entry.map_err(io_error)?.path();
+				if path.is_dir() {
+					dirs.push(path);
+				} else {
+					let relative = path.strip_prefix(root).map_err(|_| ArchiveError::InvalidPath(path.display().to_string()))?;
+					let relative = relative.to_str().ok_or_else(|| ArchiveError::InvalidPath(path.display().to_string()))?;
+					let bytes = std::fs::read(&path).map_err(|source| ArchiveError::IoError { path: path.clone(), source })?;
+					self.add_file(relative, &bytes)?;
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Writes the archive.
+	pub fn write(&self, writer: impl Write) -> Result<(), ArchiveError> {
+		self.write_entries(writer).map_err(ArchiveError::WriteError)
+	}
+
+	fn write_entries(&self, mut writer: impl Write) -> std::io::Result<()> {
+		// sort so archives are reproducible
+		let mut entries = self.entries.values().collect::<Vec<_>>();
+		entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+		writer.write_all(&MAGIC)?;
+		writer.write_all(&VERSION.to_le_bytes())?;
+		writer.write_all(&(entries.len() as u32).to_le_bytes())?;
+		let mut offset = 0u64;
+		for entry in &entries {
+			writer.write_all(&(entry.path.len() as u32).to_le_bytes())?;
+			writer.write_all(entry.path.as_bytes())?;
+			writer.write_all(&[entry.compression.to_u8()])?;
+			writer.write_all(&offset.to_le_bytes())?;
+			writer.write_all(&(entry.data.len() as u64).to_le_bytes())?;
+			writer.write_all(&entry.size.to_le_bytes())?;
+			offset += entry.data.len() as u64;
+		}
+		for entry in &entries {
+			writer.write_all(&entry.data)?;
+		}
+		Ok(())
+	}
+
+	/// Writes the archive to a file.
+	pub fn write_to_file(&self, path: impl AsRef<Path>) -> Result<(), ArchiveError> {
+		let path = path.as_ref();
+		let io_error = |source| ArchiveError::IoError { path: path.to_owned(), source };
+		let mut writer = std::io::BufWriter::new(std::fs::File::create(path).map_err(io_error)?);
+		self.write_entries(&mut writer).map_err(io_error)?;
+		writer.flush().map_err(io_error)
+	}
+}
+
+struct ArchiveEntry {
+	compression: ArchiveCompression,
+	offset: u64,
+	stored_size: u64,
+	size: u64,
+}
+
+enum ArchiveData {
+	File(Mutex<std::fs::File>, PathBuf),
+	Memory(std::borrow::Cow<'static, [u8]>),
+}
+
+/// Mounts an archive created with [`ArchiveBuilder`].
+pub struct ArchiveMount {
+	entries: FxHashMap<String, ArchiveEntry>,
+	data: ArchiveData,
+	data_start: u64,
+}
+
+fn read_u8(reader: &mut impl Read) -> std::io::Result<u8> {
+	let mut bytes = [0; 1];
+	reader.read_exact(&mut bytes)?;
+	Ok(bytes[0])
+}
+
+fn read_u32(reader: &mut impl Read) -> std::io::Result<u32> {
+	let mut bytes = [0; 4];
+	reader.read_exact(&mut bytes)?;
+	Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> std::io::Result<u64> {
+	let mut bytes = [0; 8];
+	reader.read_exact(&mut bytes)?;
+	Ok(u64::from_le_bytes(bytes))
+}
+
+/// Converts an error from reading an archive, treating data that ends early as corruption.
+///
+/// Archives in memory have no path, and can only fail by ending early.
+fn read_error(path: Option<&Path>) -> impl Fn(std::io::Error) -> ArchiveError + Copy + '_ {
+	move |source| match path {
+		Some(path) if source.kind() != ErrorKind::UnexpectedEof => ArchiveError::IoError { path: path.to_owned(), source },
+		_ => ArchiveError::Corrupt,
+	}
+}
+
+fn read_index(reader: &mut impl Read, path: Option<&Path>) -> Result<(FxHashMap<String, ArchiveEntry>, u64), ArchiveError> {
+	let io_error = read_error(path);
+	let mut magic = [0; 8];
+	reader.read_exact(&mut magic).map_err(io_error)?;
+	if magic != MAGIC {
+		return Err(ArchiveError::InvalidMagic);
+	}
+	let version = read_u32(reader).map_err(io_error)?;
+	if version != VERSION {
+		return Err(ArchiveError::UnsupportedVersion(version));
+	}
+	let n_entries = read_u32(reader).map_err(io_error)?;
+	let mut index_size = (MAGIC.len() + 8) as u64;
+	let mut entries = FxHashMap::default();
+	for _ in 0..n_entries {
+		let path_len = read_u32(reader).map_err(io_error)?;
+		let mut entry_path = vec![];
+		reader.take(path_len as u64).read_to_end(&mut entry_path).map_err(io_error)?;
+		if entry_path.len() != path_len as usize {
+			return Err(ArchiveError::Corrupt);
+		}
+		let entry_path = String::from_utf8(entry_path).map_err(|_| ArchiveError::Corrupt)?;
+		let compression = ArchiveCompression::from_u8(read_u8(reader).map_err(io_error)?).ok_or(ArchiveError::Corrupt)?;
+		let entry = ArchiveEntry {
+			compression,
+			offset: read_u64(reader).map_err(io_error)?,
+			stored_size: read_u64(reader).map_err(io_error)?,
+			size: read_u64(reader).map_err(io_error)?,
+		};
+		index_size += 4 + path_len as u64 + 1 + 8 * 3;
+		entries.insert(entry_path, entry);
+	}
+	Ok((entries, index_size))
+}
+
+impl ArchiveMount {
+	/// Opens an archive file.
+	///
+	/// Only the index is read up front, files are read from disk when they are requested.
+	pub fn open(path: impl AsRef<Path>) -> Result<Self, ArchiveError> {
+		let path = path.as_ref();
+		let mut file = std::fs::File::open(path).map_err(|source| ArchiveError::IoError { path: path.to_owned(), source })?;
+		let (entries, data_start) = read_index(&mut std::io::BufReader::new(&mut file), Some(path))?;
+		Ok(Self { entries, data: ArchiveData::File(Mutex::new(file), path.to_owned()), data_start })
+	}
+
+	/// Uses an archive that is already in memory, such as one embedded with `include_bytes!`.
+	pub fn from_bytes(bytes: impl Into<std::borrow::Cow<'static, [u8]>>) -> Result<Self, ArchiveError> {
+		let bytes = bytes.into();
+		let (entries, data_start) = read_index(&mut &*bytes, None)?;
+		Ok(Self { entries, data: ArchiveData::Memory(bytes), data_start })
+	}
+
+	/// Iterates over the paths of every file in this archive.
+	pub fn paths(&self) -> impl Iterator<Item = &str> {
+		self.entries.keys().map(|path| path.as_str())
+	}
+
+	fn read_entry(&self, entry: &ArchiveEntry) -> Result<Vec<u8>, ArchiveError> {
+		let start = self.data_start.checked_add(entry.offset).ok_or(ArchiveError::Corrupt)?;
+		let stored = match &self.data {
+			ArchiveData::File(file, path) => {
+				let io_error = read_error(Some(path));
+				let mut file = file.lock().unwrap();
+				file.seek(SeekFrom::Start(start)).map_err(io_error)?;
+				let mut stored = vec![];
+				(&mut *file).take(entry.stored_size).read_to_end(&mut stored).map_err(io_error)?;
+				if stored.len() as u64 != entry.stored_size {
+					return Err(ArchiveError::Corrupt);
+				}
+				stored
+			},
+			ArchiveData::Memory(bytes) => {
+				let end = start.checked_add(entry.stored_size).ok_or(ArchiveError::Corrupt)?;
+				bytes.get(start as usize..end as usize).ok_or(ArchiveError::Corrupt)?.to_vec()
+			},
+		};
+		let data = match entry.compression {
+			ArchiveCompression::None => stored,
+			ArchiveCompression::Deflate => miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(&stored, entry.size as usize)
+				.map_err(|_| ArchiveError::Corrupt)?,
+		};
+		if data.len() as u64 != entry.size {
+			return Err(ArchiveError::Corrupt);
+		}
+		Ok(data)
+	}
+}
+
+impl VfsMount for ArchiveMount {
+	fn read(&self, path: &str) -> std::io::Result<Option<Vec<u8>>> {
+		self.entries.get(path).map(|entry| self.read_entry(entry).map_err(|e| {
+			let kind = match &e {
+				ArchiveError::IoError { source, .. } => source.kind(),
+				_ => ErrorKind::InvalidData,
+			};
+			std::io::Error::new(kind, e)
+		})).transpose()
+	}
+
+	fn contains(&self, path: &str) -> bool {
+		self.entries.contains_key(path)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn noise(len: usize) -> Vec<u8> {
+		let mut state = 0x2545F4914F6CDD1Du64;
+		(0..len).map(|_| {
+			state ^= state << 13;
+			state ^= state >> 7;
+			state ^= state << 17;
+			state as u8
+		}).collect()
+	}
+
+	fn round_trip(builder: &ArchiveBuilder) -> ArchiveMount {
+		let mut bytes = vec![];
+		builder.write(&mut bytes).unwrap();
+		ArchiveMount::from_bytes(bytes).unwrap()
+	}
+
+	#[test]
+	fn nested_paths() {
+		let mut builder = ArchiveBuilder::new();
+		builder.add_file("a.txt", b"root").unwrap();
+		builder.add_file("textures/ui/button.png", b"button").unwrap();
+		builder.add_file("./textures\\ui//icon.png", b"icon").unwrap();
+		assert!(builder.add_file("../escape.txt", b"").is_err());
+		let archive = round_trip(&builder);
+		assert_eq!(archive.read("a.txt").unwrap().unwrap(), b"root");
+		assert_eq!(archive.read("textures/ui/button.png").unwrap().unwrap(), b"button");
+		assert_eq!(archive.read("textures/ui/icon.png").unwrap().unwrap(), b"icon");
+		assert!(archive.read("textures/ui").unwrap().is_none());
+		assert_eq!(archive.paths().count(), 3);
+	}
+
+	#[test]
+	fn large_files() {
+		let random = noise(3 * 1024 * 1024 + 17);
+		let repetitive = b"isopod".repeat(1024 * 1024);
+		for compression in [ArchiveCompression::None, ArchiveCompression::Deflate] {
+			let mut builder = ArchiveBuilder::new().compression(compression);
+			builder.add_file("random.bin", &random).unwrap();
+			builder.add_file("data/repetitive.bin", &repetitive).unwrap();
+			builder.add_file("empty.bin", &[]).unwrap();
+			let archive = round_trip(&builder);
+			assert_eq!(archive.read("random.bin").unwrap().unwrap(), random);
+			assert_eq!(archive.read("data/repetitive.bin").unwrap().unwrap(), repetitive);
+			assert_eq!(archive.read("empty.bin").unwrap().unwrap(), b"");
+		}
+	}
+
+	#[test]
+	fn dir_to_file() {
+		let dir = std::env::temp_dir().join(format!("isopod_archive_test_{}", std::process::id()));
+		let _ = std::fs::remove_dir_all(&dir);
+		std::fs::create_dir_all(dir.join("assets/levels/one")).unwrap();
+		std::fs::write(dir.join("assets/top.txt"), b"top").unwrap();
+		std::fs::write(dir.join("assets/levels/one/map.ron"), noise(100_000)).unwrap();
+		let mut builder = ArchiveBuilder::new();
+		builder.add_dir(dir.join("assets")).unwrap();
+		builder.write_to_file(dir.join("assets.pak")).unwrap();
+
+		let archive = ArchiveMount::open(dir.join("assets.pak")).unwrap();
+		assert_eq!(archive.read("top.txt").unwrap().unwrap(), b"top");
+		assert_eq!(archive.read("levels/one/map.ron").unwrap().unwrap(), noise(100_000));
+
+		let mut vfs = Vfs::new();
+		vfs.mount(0, Box::new(archive));
+		assert!(vfs.read("levels/one/../one/map.ron").is_err());
+		assert_eq!(vfs.read("levels/one/map.ron").unwrap(), noise(100_000));
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn corrupt_archives() {
+		assert!(matches!(ArchiveMount::from_bytes(b"not an archive".to_vec()), Err(ArchiveError::InvalidMagic)));
+		let mut builder = ArchiveBuilder::new().compression(ArchiveCompression::None);
+		builder.add_file("file.bin", &noise(1000)).unwrap();
+		let mut bytes = vec![];
+		builder.write(&mut bytes).unwrap();
+		bytes.truncate(bytes.len() - 10);
+		let archive = ArchiveMount::from_bytes(bytes.clone()).unwrap();
+		assert!(archive.read("file.bin").is_err());
+		bytes.truncate(20);
+		assert!(matches!(ArchiveMount::from_bytes(bytes), Err(ArchiveError::Corrupt)));
+	}
+
+	#[test]
+	fn io_errors() {
+		let path = std::env::temp_dir().join("isopod_archive_test_missing.pak");
+		let error = ArchiveMount::open(&path).err().unwrap();
+		assert!(matches!(&error, ArchiveError::IoError { path: error_path, .. } if *error_path == path));
+		assert!(error.to_string().contains("isopod_archive_test_missing.pak"));
+		assert!(std::error::Error::source(&error).is_some());
+	}
+}
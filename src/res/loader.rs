@@ -0,0 +1,111 @@
+use std::{any::Any, sync::{mpsc, Arc, Mutex, RwLock}};
+
+use super::{DecodeCtx, Vfs};
+
+pub(crate) type DecodeFn = fn(Vec<u8>, &DecodeCtx) -> anyhow::Result<Box<dyn Any + Send>>;
+
+pub(crate) struct LoadJob {
+	/// Increases with every submitted job, so it also orders loads of the same resource.
+	pub id: usize,
+	pub path: String,
+	pub decode: DecodeFn,
+}
+
+pub(crate) struct LoadResult {
+	pub id: usize,
+	pub decoded: anyhow::Result<Box<dyn Any + Send>>,
+}
+
+const MAX_LOADER_THREADS: usize = 4;
+
+/// Pool of threads that read and decode resources in the background.
+///
+/// Threads exit once the loader is dropped.
+pub(crate) struct Loader {
+	jobs: mpsc::Sender<LoadJob>,
+	results: mpsc::Receiver<LoadResult>,
+}
+
+impl Loader {
+	pub fn new(vfs: Arc<RwLock<Vfs>>) -> Self {
+		let (jobs, job_receiver) = mpsc::channel::<LoadJob>();
+		let (result_sender, results) = mpsc::channel();
+		let job_receiver = Arc::new(Mutex::new(job_receiver));
+		let n_threads = std::thread::available_parallelism().map_or(1, |n| n.get().saturating_sub(1)).clamp(1, MAX_LOADER_THREADS);
+		for i in 0..n_threads {
+			let job_receiver = job_receiver.clone();
+			let result_sender = result_sender.clone();
+			let vfs = vfs.clone();
+			std::thread::Builder::new().name(format!("isopod loader {}", i)).spawn(move || loop {
+				// lock is released before decoding so other threads can pick up jobs
+				let job = match job_receiver.lock().unwrap().recv() {
+					Ok(job) => job,
+					Err(_) => break,
+				};
+				// the filesystem is only locked while taking the snapshot so mounting isn't blocked by slow reads
+				let mounts = vfs.read().unwrap().snapshot();
+				// a panicking decoder fails the load instead of killing the thread and leaving the resource pending,
+				// and nothing the closure owns is used after a panic
+				let decoded = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
+					let bytes = Vfs::read_from(&mounts, &job.path);
+					let ctx = DecodeCtx::new(job.path, mounts);
+					bytes.map_err(anyhow::Error::from).and_then(|bytes| (job.decode)(bytes, &ctx))
+				})).unwrap_or_else(|payload| Err(anyhow::anyhow!("panicked while loading: {}", panic_message(&*payload))));
+				if result_sender.send(LoadResult { id: job.id, decoded }).is_err() {
+					break;
+				}
+			}).unwrap();
+		}
+		Self { jobs, results }
+	}
+
+	pub fn submit(&self, job: LoadJob) {
+		// can only fail if every thread has panicked
+		let _ = self.jobs.send(job);
+	}
+
+	pub fn try_recv(&self) -> Option<LoadResult> {
+		self.results.try_recv().ok()
+	}
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+	if let Some(message) = payload.downcast_ref::<&str>() {
+		message
+	} else if let Some(message) = payload.downcast_ref::<String>() {
+		message
+	} else {
+		"unknown panic"
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::EmbeddedMount;
+
+	fn recv(loader: &Loader) -> LoadResult {
+		for _ in 0..1000 {
+			if let Some(result) = loader.try_recv() {
+				return result;
+			}
+			std::thread::sleep(std::time::Duration::from_millis(1));
+		}
+		panic!("load never finished");
+	}
+
+	#[test]
+	fn decode_panics() {
+		let mut vfs = Vfs::new();
+		vfs.mount(0, Box::new(EmbeddedMount::new().with("text.txt", b"text")));
+		let loader = Loader::new(Arc::new(RwLock::new(vfs)));
+		loader.submit(LoadJob { id: 0, path: "text.txt".to_owned(), decode: |_, _| panic!("bad data") });
+		let result = recv(&loader);
+		assert_eq!(result.id, 0);
+		assert_eq!(result.decoded.err().unwrap().to_string(), "panicked while loading: bad data");
+		// the thread that panicked is still running
+		loader.submit(LoadJob { id: 1, path: "text.txt".to_owned(), decode: |bytes, _| Ok(Box::new(bytes)) });
+		let result = recv(&loader);
+		assert_eq!(*result.decoded.unwrap().downcast::<Vec<u8>>().unwrap(), b"text");
+	}
+}
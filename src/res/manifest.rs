@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use rustc_hash::FxHashMap;
+
+use crate::EngineCtx;
+use super::{AnyResInner, LoadGroup, Resource, VfsError};
+
+// Manifest layout:
+//
+// (
+//   groups: {
+//     "ui": [
+//       (type: "texture", path: "ui/button.png"),
+//       (type: "shader", path: "ui/button.shader"),
+//     ],
+//   },
+// )
+
+#[derive(serde::Deserialize)]
+struct Manifest {
+	groups: FxHashMap<String, Vec<ManifestEntry>>,
+}
+
+#[derive(serde::Deserialize, Clone)]
+struct ManifestEntry {
+	#[serde(rename = "type")]
+	type_name: String,
+	path: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ManifestError {
+	#[error("failed to read manifest")]
+	VfsError(#[from] VfsError),
+	#[error("failed to parse manifest")]
+	ParseError(#[from] ron::error::SpannedError),
+	#[error("unknown resource type \"{0}\" (types can be added with `EngineCtx::register_resource_type`)")]
+	UnknownType(String),
+	#[error("no group named \"{0}\" in any loaded manifest")]
+	UnknownGroup(String),
+}
+
+type ManifestLoadFn = fn(&EngineCtx, String) -> Arc<dyn AnyResInner>;
+
+fn load_erased<T: Resource>(ctx: &EngineCtx, path: String) -> Arc<dyn AnyResInner> {
+	ctx.load::<T>(path).inner
+}
+
+/// Groups of resources declared in manifest files.
+pub(crate) struct Manifests {
+	types: FxHashMap<String, ManifestLoadFn>,
+	groups: FxHashMap<String, Vec<ManifestEntry>>,
+	loaded: FxHashMap<String, LoadGroup>,
+}
+
+impl Manifests {
+	pub fn new() -> Self {
+		let mut manifests = Self { types: FxHashMap::default(), groups: FxHashMap::default(), loaded: FxHashMap::default() };
+		manifests.register_type::<crate::gfx::GPUTexture2D>("texture");
+		manifests.register_type::<crate::gfx::Texture<crate::math::U8Vec4>>("image");
+		manifests.register_type::<crate::gfx::ShaderSource>("shader");
+		manifests.register_type::<crate::gltf::GltfDecoded>("gltf");
+		manifests
+	}
+
+	pub fn register_type<T: Resource>(&mut self, name: impl Into<String>) {
+		self.types.insert(name.into(), load_erased::<T>);
+	}
+
+	/// Adds the groups in a manifest, replacing any existing groups with the same names.
+	///
+	/// Groups that are already loaded keep their resources until they are unloaded.
+	pub fn add(&mut self, data: &[u8]) -> Result<(), ManifestError> {
+		let manifest: Manifest = ron::de::from_bytes(data)?;
+		for entry in manifest.groups.values().flatten() {
+			if !self.types.contains_key(&entry.type_name) {
+				return Err(ManifestError::UnknownType(entry.type_name.clone()));
+			}
+		}
+		self.groups.extend(manifest.groups);
+		Ok(())
+	}
+
+	/// Starts loading every resource in a group.
+	///
+	/// Returns the existing group if it is already loaded.
+	pub fn load_group(&mut self, ctx: &EngineCtx, name: &str) -> Result<LoadGroup, ManifestError> {
+		if let Some(group) = self.loaded.get(name) {
+			return Ok(group.clone());
+		}
+		let entries = self.groups.get(name).ok_or_else(|| ManifestError::UnknownGroup(name.to_owned()))?;
+		let group = LoadGroup {
+			members: entries.iter().map(|entry| self.types[&entry.type_name](ctx, entry.path.clone())).collect(),
+		};
+		self.loaded.insert(name.to_owned(), group.clone());
+		Ok(group)
+	}
+
+	pub fn unload_group(&mut self, name: &str) -> bool {
+		self.loaded.remove(name).is_some()
+	}
+
+	pub fn is_group_loaded(&self, name: &str) -> bool {
+		self.loaded.contains_key(name)
+	}
+}
@@ -1,18 +1,355 @@
-use std::{any::{Any, TypeId}, cell::RefCell, ops::Deref, sync::{Arc, Mutex, Weak}};
-use qcell::{QCell, QCellOwner, TCell};
+use std::{any::{Any, TypeId}, cell::{Cell, Ref, RefCell, RefMut}, ops::{Deref, DerefMut}, sync::{atomic::{AtomicU8, AtomicUsize, Ordering}, Arc, RwLock, Weak}, time::SystemTime};
 use rustc_hash::FxHashMap;
 
 use crate::EngineCtx;
 
+mod vfs;
+mod loader;
+mod archive;
+mod manifest;
+pub use vfs::*;
+pub use archive::*;
+pub use manifest::ManifestError;
+use loader::*;
+use manifest::Manifests;
+
 struct ResourceMarker;
 
+type DefaultCell<T> = Arc<qcell::TCell<ResourceMarker, T>>;
+
+struct ResInner<T> {
+	state: AtomicU8,
+	/// Id of the most recently submitted load whose result has been applied, so older loads that finish later can be ignored.
+	generation: AtomicUsize,
+	/// `None` while the resource shows the default for its type, which is shared between every resource of the type.
+	value: qcell::TCell<ResourceMarker, Option<T>>,
+	default: Option<DefaultCell<T>>,
+}
+
+/// Shared handle to a [`Resource`].
+///
+/// The contents can be accessed with [`EngineCtx::read`] and modified with [`EngineCtx::write`].
 pub struct Res<T> {
-	inner: Arc<qcell::TCell<ResourceMarker, T>>,
+	inner: Arc<ResInner<T>>,
+}
+
+impl<T> Clone for Res<T> {
+	fn clone(&self) -> Self {
+		Self { inner: self.inner.clone() }
+	}
+}
+
+impl<T: Resource> Res<T> {
+	fn new(value: T) -> Self {
+		Self { inner: Arc::new(ResInner { state: AtomicU8::new(LoadState::Loaded as u8), generation: AtomicUsize::new(0), value: qcell::TCell::new(Some(value)), default: None }) }
+	}
+
+	fn pending(default: DefaultCell<T>) -> Self {
+		Self { inner: Arc::new(ResInner { state: AtomicU8::new(LoadState::Pending as u8), generation: AtomicUsize::new(0), value: qcell::TCell::new(None), default: Some(default) }) }
+	}
+
+	/// Returns whether this resource has finished loading.
+	pub fn load_state(&self) -> LoadState {
+		self.inner.load_state()
+	}
+}
+
+/// Guard for reading a resource, returned by [`EngineCtx::read`].
+pub struct ResRef<'a, T> {
+	owner: Ref<'a, qcell::TCellOwner<ResourceMarker>>,
+	inner: &'a ResInner<T>,
+}
+
+impl<T> Deref for ResRef<'_, T> {
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		match self.inner.value.ro(&self.owner) {
+			Some(value) => value,
+			None => self.inner.default.as_ref().unwrap().ro(&self.owner),
+		}
+	}
+}
+
+/// Guard for modifying a resource, returned by [`EngineCtx::write`].
+///
+/// Always points to the resource's own value, never to the shared default.
+pub struct ResMut<'a, T> {
+	owner: RefMut<'a, qcell::TCellOwner<ResourceMarker>>,
+	cell: &'a qcell::TCell<ResourceMarker, Option<T>>,
+}
+
+impl<T> Deref for ResMut<'_, T> {
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		self.cell.ro(&self.owner).as_ref().unwrap()
+	}
+}
+
+impl<T> DerefMut for ResMut<'_, T> {
+	fn deref_mut(&mut self) -> &mut T {
+		self.cell.rw(&mut self.owner).as_mut().unwrap()
+	}
+}
+
+/// Loading progress of a [`Res`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LoadState {
+	/// The resource is being loaded in the background and currently holds its [default](Resource::default).
+	Pending,
+	/// The resource has been loaded.
+	Loaded,
+	/// The resource failed to load and holds its [default](Resource::default).
+	Failed,
+}
+
+impl LoadState {
+	fn from_u8(v: u8) -> Self {
+		match v {
+			0 => Self::Pending,
+			1 => Self::Loaded,
+			_ => Self::Failed,
+		}
+	}
+}
+
+trait AnyResInner: Send + Sync {
+	fn load_state(&self) -> LoadState;
+	fn type_name(&self) -> &'static str;
+	fn memory_size(&self, owner: &qcell::TCellOwner<ResourceMarker>) -> usize;
+	fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync>;
+}
+
+impl<T: Resource> AnyResInner for ResInner<T> {
+	fn load_state(&self) -> LoadState {
+		LoadState::from_u8(self.state.load(Ordering::Acquire))
+	}
+
+	fn type_name(&self) -> &'static str {
+		std::any::type_name::<T>()
+	}
+
+	fn memory_size(&self, owner: &qcell::TCellOwner<ResourceMarker>) -> usize {
+		// the shared default isn't counted against each resource using it
+		self.value.ro(owner).as_ref().map_or(0, T::memory_size)
+	}
+
+	fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync> {
+		self
+	}
+}
+
+/// Information about a loaded resource, returned by [`EngineCtx::resources`].
+#[derive(Clone, Debug)]
+pub struct ResourceInfo {
+	pub type_name: &'static str,
+	pub path: String,
+	/// Number of [`Res`] handles (including ones held by [`LoadGroup`]s) pointing to the resource.
+	pub strong_count: usize,
+	/// Approximate memory used by the resource in bytes (see [`Resource::memory_size`]).
+	pub memory_size: usize,
+	pub load_state: LoadState,
+}
+
+/// Collection of resources that can be checked on together, such as for a loading screen.
+#[derive(Default, Clone)]
+pub struct LoadGroup {
+	members: Vec<Arc<dyn AnyResInner>>,
+}
+
+impl LoadGroup {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a resource to this group.
+	///
+	/// The group holds a strong reference, so the resource will stay loaded as long as the group exists.
+	pub fn add<T: Resource>(&mut self, res: &Res<T>) {
+		self.members.push(res.inner.clone());
+	}
+
+	/// Adds a resource to this group.
+	pub fn with<T: Resource>(mut self, res: &Res<T>) -> Self {
+		self.add(res);
+		self
+	}
+
+	/// Returns the fraction of resources in this group that are no longer pending.
+	///
+	/// Empty groups are considered finished.
+	pub fn progress(&self) -> f32 {
+		if self.members.is_empty() {
+			1.
+		} else {
+			self.members.iter().filter(|m| m.load_state() != LoadState::Pending).count() as f32 / self.members.len() as f32
+		}
+	}
+
+	/// Returns `true` once every resource in this group has either loaded or failed.
+	///
+	/// There is no way to block until a group is finished, since loads are only applied between frames.
+	/// Instead, this should be polled every frame (such as from a loading screen), or [`EngineCtx::try_load`] used for resources that are needed right away.
+	pub fn is_finished(&self) -> bool {
+		self.members.iter().all(|m| m.load_state() != LoadState::Pending)
+	}
+
+	/// Returns `true` if any resource in this group failed to load.
+	pub fn has_failed(&self) -> bool {
+		self.members.iter().any(|m| m.load_state() == LoadState::Failed)
+	}
 }
 
 pub trait Resource: 'static + Send + Sync + Sized {
-	fn load(data: &[u8], ctx: &EngineCtx) -> Result<Self, String>;
+	/// Intermediate data produced by [`decode`](Resource::decode).
+	type Decoded: 'static + Send;
+	/// Decodes the bytes of the file the resource is loaded from.
+	///
+	/// This is called on a background loader thread, so it should do as much of the work as possible.
+	/// Errors are wrapped in a [`ResourceError`] that records the path and resource type, so they only need to describe the cause.
+	fn decode(data: Vec<u8>) -> anyhow::Result<Self::Decoded>;
+	/// Decodes a resource that references other files, which can be read through `ctx`.
+	///
+	/// The default implementation calls [`decode`](Resource::decode), which is enough for resources made from a single file.
+	fn decode_with(data: Vec<u8>, _ctx: &DecodeCtx) -> anyhow::Result<Self::Decoded> {
+		Self::decode(data)
+	}
+	/// Creates the resource from decoded data on the main thread.
+	fn load(decoded: Self::Decoded, ctx: &EngineCtx) -> anyhow::Result<Self>;
+	/// Value used in place of resources that are still loading or failed to load.
 	fn default(ctx: &EngineCtx) -> Self;
+	/// Approximate number of bytes used by this resource, including heap and GPU allocations.
+	///
+	/// Only used for reporting, so it does not need to be exact.
+	fn memory_size(&self) -> usize {
+		std::mem::size_of::<Self>()
+	}
+}
+
+/// Error produced when a [`Resource`] fails to load.
+#[derive(Debug, thiserror::Error)]
+#[error("failed to load {type_name} from \"{path}\"")]
+pub struct ResourceError {
+	pub path: String,
+	pub type_name: &'static str,
+	#[source]
+	pub cause: anyhow::Error,
+}
+
+impl ResourceError {
+	fn new<T: Resource>(path: &str, cause: anyhow::Error) -> Self {
+		Self { path: path.to_owned(), type_name: std::any::type_name::<T>(), cause }
+	}
+
+	/// Iterates over this error and every error that caused it.
+	pub fn chain(&self) -> impl Iterator<Item = &(dyn std::error::Error + 'static)> {
+		std::iter::successors(Some(self as &(dyn std::error::Error + 'static)), |e| e.source())
+	}
+
+	/// Formats this error along with its causes on a single line.
+	pub fn report(&self) -> String {
+		self.chain().map(|e| e.to_string()).collect::<Vec<_>>().join(": ")
+	}
+}
+
+/// Access to other files while [decoding](Resource::decode_with) a resource, such as the buffers referenced by a `.gltf` file.
+pub struct DecodeCtx {
+	path: String,
+	mounts: Vec<Arc<dyn VfsMount>>,
+}
+
+impl DecodeCtx {
+	pub(crate) fn new(path: String, mounts: Vec<Arc<dyn VfsMount>>) -> Self {
+		Self { path, mounts }
+	}
+
+	/// Path of the resource being decoded.
+	pub fn path(&self) -> &str {
+		&self.path
+	}
+
+	/// Reads a file relative to the directory of the resource being decoded.
+	///
+	/// For example, `../textures/wood.png` for a resource at `models/chair.gltf` reads `textures/wood.png`.
+	pub fn read_relative(&self, path: &str) -> Result<Vec<u8>, VfsError> {
+		let resolved = Vfs::resolve_relative(&self.path, path).ok_or_else(|| VfsError::InvalidPath(path.to_owned()))?;
+		Vfs::read_from(&self.mounts, &resolved)
+	}
+}
+
+fn decode_any<T: Resource>(data: Vec<u8>, ctx: &DecodeCtx) -> anyhow::Result<Box<dyn Any + Send>> {
+	Ok(Box::new(T::decode_with(data, ctx)?))
+}
+
+/// Main thread side of a resource being loaded in the background.
+trait PendingLoad {
+	/// Creates the resource from decoded data.
+	fn finish(&self, decoded: anyhow::Result<Box<dyn Any + Send>>, ctx: &EngineCtx) -> Result<Box<dyn Any>, ResourceError>;
+	/// Moves a finished resource into its [`Res`], unless a load that was submitted later has already been applied.
+	fn apply(&self, value: Box<dyn Any>, generation: usize, owner: &mut qcell::TCellOwner<ResourceMarker>);
+	fn fail(&self, generation: usize);
+	/// Returns `false` once every [`Res`] for the resource has been dropped.
+	fn is_alive(&self) -> bool;
+	/// Creates a load that will replace the resource if it succeeds and leave it alone otherwise.
+	fn reload(&self) -> Box<dyn PendingLoad>;
+	/// Address of the resource this load is for, used to compare loads of different types.
+	fn target(&self) -> *const ();
+}
+
+struct TypedPendingLoad<T> {
+	path: String,
+	target: Weak<ResInner<T>>,
+	is_reload: bool,
+}
+
+impl<T: Resource> PendingLoad for TypedPendingLoad<T> {
+	fn finish(&self, decoded: anyhow::Result<Box<dyn Any + Send>>, ctx: &EngineCtx) -> Result<Box<dyn Any>, ResourceError> {
+		decoded.and_then(|decoded| T::load(*decoded.downcast::<T::Decoded>().unwrap(), ctx))
+			.map(|v| Box::new(v) as Box<dyn Any>)
+			.map_err(|cause| ResourceError::new::<T>(&self.path, cause))
+	}
+
+	fn apply(&self, value: Box<dyn Any>, generation: usize, owner: &mut qcell::TCellOwner<ResourceMarker>) {
+		// loads can finish out of order on different threads, such as a reload finishing before the initial load
+		if let Some(target) = self.target.upgrade().filter(|target| target.generation.load(Ordering::Acquire) <= generation) {
+			*target.value.rw(owner) = Some(*value.downcast::<T>().unwrap());
+			target.generation.store(generation, Ordering::Release);
+			target.state.store(LoadState::Loaded as u8, Ordering::Release);
+		}
+	}
+
+	fn fail(&self, generation: usize) {
+		if let Some(target) = self.target.upgrade().filter(|target| !self.is_reload && target.generation.load(Ordering::Acquire) <= generation) {
+			target.state.store(LoadState::Failed as u8, Ordering::Release);
+		}
+	}
+
+	fn is_alive(&self) -> bool {
+		self.target.strong_count() > 0
+	}
+
+	fn reload(&self) -> Box<dyn PendingLoad> {
+		Box::new(TypedPendingLoad { path: self.path.clone(), target: self.target.clone(), is_reload: true })
+	}
+
+	fn target(&self) -> *const () {
+		self.target.as_ptr() as *const ()
+	}
+}
+
+/// A loaded resource whose file is checked for changes.
+struct WatchedFile {
+	path: String,
+	modified: Option<SystemTime>,
+	decode: DecodeFn,
+	load: Box<dyn PendingLoad>,
+}
+
+struct FinishedLoad {
+	pending: Box<dyn PendingLoad>,
+	generation: usize,
+	value: Box<dyn Any>,
 }
 
 #[derive(PartialEq, Eq, Hash)]
@@ -22,55 +359,305 @@ struct RID {
 }
 
 pub(crate) struct ResourceStorage {
-	resources: RefCell<FxHashMap<RID, Weak<dyn Any + Send + Sync>>>,
+	resources: RefCell<FxHashMap<RID, Weak<dyn AnyResInner>>>,
+	resource_owner: RefCell<qcell::TCellOwner<ResourceMarker>>,
+	vfs: Arc<RwLock<Vfs>>,
+	loader: Loader,
+	pending: RefCell<FxHashMap<usize, Box<dyn PendingLoad>>>,
+	finished: RefCell<Vec<FinishedLoad>>,
+	next_load_id: Cell<usize>,
+	watched: RefCell<Vec<WatchedFile>>,
+	hot_reload: Cell<bool>,
+	manifests: RefCell<Manifests>,
 	defaults: RefCell<FxHashMap<TypeId, Arc<dyn Any + Send + Sync>>>,
-	resource_owner: qcell::TCellOwner<ResourceMarker>,
 }
 
 impl ResourceStorage {
 	pub fn new() -> Self {
+		let vfs = Arc::new(RwLock::new(Vfs::new()));
 		Self {
-			resource_owner: qcell::TCellOwner::new(),
+			// only one owner can exist at a time, so engines started on other threads (such as by tests running in parallel) wait for this one to end
+			resource_owner: RefCell::new(qcell::TCellOwner::wait_for_new()),
 			resources: RefCell::new(FxHashMap::default()),
+			loader: Loader::new(vfs.clone()),
+			vfs,
+			pending: RefCell::new(FxHashMap::default()),
+			finished: RefCell::new(Vec::new()),
+			next_load_id: Cell::new(0),
+			watched: RefCell::new(Vec::new()),
+			hot_reload: Cell::new(cfg!(debug_assertions)),
+			manifests: RefCell::new(Manifests::new()),
 			defaults: RefCell::new(FxHashMap::default()),
 		}
 	}
 
-	pub fn read<'a, T: Resource>(&'a self, res: &'a Res<T>) -> &'a T {
-		res.inner.ro(&self.resource_owner)
+	pub fn set_hot_reload(&self, enabled: bool) {
+		self.hot_reload.set(enabled);
 	}
 
-	pub fn get<T: Resource>(&self, ctx: &EngineCtx, path: impl Into<String>) -> Res<T> {
-		let key = RID { type_id: TypeId::of::<T>(), path: path.into() };
-		let existing = self.resources.borrow().get(&key).map(|weak| weak.upgrade()).flatten();
-		if let Some(v) = existing {
-			Res { inner: v.downcast::<qcell::TCell<ResourceMarker,T>>().unwrap() }
+	pub fn mount(&self, priority: i32, mount: Box<dyn VfsMount>) -> MountID {
+		self.vfs.write().unwrap().mount(priority, mount)
+	}
+
+	pub fn unmount(&self, id: MountID) -> bool {
+		self.vfs.write().unwrap().unmount(id)
+	}
+
+	pub fn read_file(&self, path: &str) -> Result<Vec<u8>, VfsError> {
+		self.vfs.read().unwrap().read(path)
+	}
+
+	pub fn read<'a, T: Resource>(&'a self, res: &'a Res<T>) -> ResRef<'a, T> {
+		ResRef { owner: self.resource_owner.borrow(), inner: &res.inner }
+	}
+
+	/// Returns `None` if any guard returned by [`read`](Self::read) or [`write`](Self::write) is still alive.
+	///
+	/// Any loads or reloads for the resource are cancelled so they don't overwrite the changes.
+	pub fn try_write<'a, T: Resource>(&'a self, ctx: &EngineCtx, res: &'a Res<T>) -> Option<ResMut<'a, T>> {
+		let has_value = res.inner.value.ro(&*self.resource_owner.try_borrow_mut().ok()?).is_some();
+		if !has_value {
+			// modifying the shared default would change every other resource using it, so this one gets its own copy
+			let value = T::default(ctx);
+			*res.inner.value.rw(&mut *self.resource_owner.try_borrow_mut().ok()?) = Some(value);
+		}
+		self.cancel_loads(Arc::as_ptr(&res.inner) as *const ());
+		res.inner.state.store(LoadState::Loaded as u8, Ordering::Release);
+		Some(ResMut { owner: self.resource_owner.try_borrow_mut().ok()?, cell: &res.inner.value })
+	}
+
+	pub fn write<'a, T: Resource>(&'a self, ctx: &EngineCtx, res: &'a Res<T>) -> ResMut<'a, T> {
+		self.try_write(ctx, res).expect("resource written while a read or write guard is alive")
+	}
+
+	/// Cancels any loads, finished loads and hot reloads whose results would replace a resource.
+	fn cancel_loads(&self, target: *const ()) {
+		self.pending.borrow_mut().retain(|_, load| load.target() != target);
+		self.finished.borrow_mut().retain(|finished| finished.pending.target() != target);
+		self.watched.borrow_mut().retain(|file| file.load.target() != target);
+	}
+
+	/// Returns the default for a resource type, creating it the first time it is needed.
+	fn default<T: Resource>(&self, ctx: &EngineCtx) -> DefaultCell<T> {
+		let existing = self.defaults.borrow().get(&TypeId::of::<T>()).cloned();
+		if let Some(default) = existing {
+			return default.downcast().unwrap();
+		}
+		let default = Arc::new(qcell::TCell::new(T::default(ctx)));
+		self.defaults.borrow_mut().insert(TypeId::of::<T>(), default.clone());
+		default
+	}
+
+	/// Stores a resource under a path.
+	///
+	/// If the resource already exists, its contents are replaced and any loads or reloads for it are cancelled.
+	pub fn insert<T: Resource>(&self, path: impl Into<String>, value: T) -> Res<T> {
+		let key = Self::key::<T>(path.into());
+		if let Some(res) = self.existing::<T>(&key) {
+			self.cancel_loads(Arc::as_ptr(&res.inner) as *const ());
+			*res.inner.value.rw(&mut self.resource_owner.borrow_mut()) = Some(value);
+			res.inner.state.store(LoadState::Loaded as u8, Ordering::Release);
+			res
 		} else {
-			match T::load(&[], ctx) {
+			let res = Res::new(value);
+			let res_weak = Arc::downgrade(&res.inner) as Weak<dyn AnyResInner>;
+			self.resources.borrow_mut().insert(key, res_weak);
+			res
+		}
+	}
+
+	pub fn register_resource_type<T: Resource>(&self, name: impl Into<String>) {
+		self.manifests.borrow_mut().register_type::<T>(name);
+	}
+
+	/// Reads a manifest through the mounted filesystem and adds its groups.
+	pub fn load_manifest(&self, path: &str) -> Result<(), ManifestError> {
+		let data = self.read_file(path)?;
+		self.manifests.borrow_mut().add(&data)
+	}
+
+	pub fn load_group(&self, ctx: &EngineCtx, name: &str) -> Result<LoadGroup, ManifestError> {
+		self.manifests.borrow_mut().load_group(ctx, name)
+	}
+
+	pub fn unload_group(&self, name: &str) -> bool {
+		self.manifests.borrow_mut().unload_group(name)
+	}
+
+	pub fn is_group_loaded(&self, name: &str) -> bool {
+		self.manifests.borrow().is_group_loaded(name)
+	}
+
+	fn key<T: Resource>(path: String) -> RID {
+		RID { type_id: TypeId::of::<T>(), path: Vfs::normalize_path(&path).unwrap_or(path) }
+	}
+
+	fn existing<T: Resource>(&self, key: &RID) -> Option<Res<T>> {
+		let existing = self.resources.borrow().get(key).and_then(|weak| weak.upgrade());
+		existing.map(|v| Res { inner: v.into_any().downcast::<ResInner<T>>().unwrap() })
+	}
+
+	/// Lists every resource that is still alive, sorted by memory size with the largest first.
+	pub fn list(&self) -> Vec<ResourceInfo> {
+		let owner = self.resource_owner.borrow();
+		let mut resources = self.resources.borrow_mut();
+		resources.retain(|_, weak| weak.strong_count() > 0);
+		let mut list = resources.iter().filter_map(|(key, weak)| {
+			let strong_count = weak.strong_count();
+			weak.upgrade().map(|inner| ResourceInfo {
+				type_name: inner.type_name(),
+				path: key.path.clone(),
+				strong_count,
+				memory_size: inner.memory_size(&owner),
+				load_state: inner.load_state(),
+			})
+		}).collect::<Vec<_>>();
+		list.sort_by(|a, b| b.memory_size.cmp(&a.memory_size).then_with(|| a.path.cmp(&b.path)));
+		list
+	}
+
+	/// Starts loading a resource in the background.
+	pub fn get<T: Resource>(&self, ctx: &EngineCtx, path: impl Into<String>) -> Res<T> {
+		let key = Self::key::<T>(path.into());
+		if let Some(res) = self.existing(&key) {
+			return res;
+		}
+		let res = Res::pending(self.default::<T>(ctx));
+		let load = TypedPendingLoad { path: key.path.clone(), target: Arc::downgrade(&res.inner), is_reload: false };
+		self.watch(&key.path, decode_any::<T>, load.reload());
+		self.submit(key.path.clone(), decode_any::<T>, Box::new(load));
+		let res_weak = Arc::downgrade(&res.inner) as Weak<dyn AnyResInner>;
+		self.resources.borrow_mut().insert(key, res_weak);
+		res
+	}
+
+	fn submit(&self, path: String, decode: DecodeFn, load: Box<dyn PendingLoad>) {
+		let id = self.next_load_id.get();
+		self.next_load_id.set(id + 1);
+		self.loader.submit(LoadJob { id, path, decode });
+		self.pending.borrow_mut().insert(id, load);
+	}
+
+	fn watch(&self, path: &str, decode: DecodeFn, load: Box<dyn PendingLoad>) {
+		let modified = self.vfs.read().unwrap().modified(path);
+		self.watched.borrow_mut().push(WatchedFile { path: path.to_owned(), modified, decode, load });
+	}
+
+	/// Starts reloading any resources whose files have changed since they were loaded.
+	pub fn poll_changes(&self) {
+		if !self.hot_reload.get() {
+			return;
+		}
+		let mut watched = self.watched.borrow_mut();
+		watched.retain(|file| file.load.is_alive());
+		let vfs = self.vfs.read().unwrap();
+		for file in watched.iter_mut() {
+			let modified = vfs.modified(&file.path);
+			if modified != file.modified {
+				file.modified = modified;
+				self.submit(file.path.clone(), file.decode, file.load.reload());
+			}
+		}
+	}
+
+	/// Loads a resource on the current thread.
+	///
+	/// Resources that are still loading in the background or failed to load are loaded again here, so that the value or the error can be returned.
+	pub fn try_get<T: Resource>(&self, ctx: &EngineCtx, path: impl Into<String>) -> Result<Res<T>, ResourceError> {
+		let key = Self::key::<T>(path.into());
+		let existing = self.existing::<T>(&key);
+		if let Some(res) = existing.as_ref().filter(|res| res.load_state() == LoadState::Loaded) {
+			return Ok(res.clone());
+		}
+		let mounts = self.vfs.read().unwrap().snapshot();
+		let decode_ctx = DecodeCtx::new(key.path.clone(), mounts.clone());
+		let v = Vfs::read_from(&mounts, &key.path).map_err(anyhow::Error::from)
+			.and_then(|bytes| T::decode_with(bytes, &decode_ctx))
+			.and_then(|decoded| T::load(decoded, ctx))
+			.map_err(|cause| ResourceError::new::<T>(&key.path, cause));
+		if let Some(res) = existing {
+			// the background load is no longer needed, but hot reloading is kept
+			let target = Arc::as_ptr(&res.inner) as *const ();
+			self.pending.borrow_mut().retain(|_, load| load.target() != target);
+			self.finished.borrow_mut().retain(|finished| finished.pending.target() != target);
+			return match v {
 				Ok(v) => {
-					let res = Res { inner: Arc::new(qcell::TCell::new(v)) };
-					let res_weak = Arc::downgrade(&res.inner);
-					self.resources.borrow_mut().insert(key, res_weak);
-					res
+					*res.inner.value.rw(&mut self.resource_owner.borrow_mut()) = Some(v);
+					res.inner.state.store(LoadState::Loaded as u8, Ordering::Release);
+					Ok(res)
 				},
 				Err(error) => {
-					// will handle error here in the future
-					self.default::<T>(ctx)
+					res.inner.state.store(LoadState::Failed as u8, Ordering::Release);
+					Err(error)
+				},
+			};
+		}
+		let res = Res::new(v?);
+		self.watch(&key.path, decode_any::<T>, Box::new(TypedPendingLoad { path: key.path.clone(), target: Arc::downgrade(&res.inner), is_reload: true }));
+		let res_weak = Arc::downgrade(&res.inner) as Weak<dyn AnyResInner>;
+		self.resources.borrow_mut().insert(key, res_weak);
+		Ok(res)
+	}
+
+	/// Finishes resources that have been decoded by loader threads.
+	///
+	/// They will be swapped into their [`Res`] by the next call to [`apply_loads`](ResourceStorage::apply_loads).
+	pub fn finish_loads(&self, ctx: &EngineCtx) {
+		while let Some(result) = self.loader.try_recv() {
+			let Some(pending) = self.pending.borrow_mut().remove(&result.id) else {
+				continue;
+			};
+			match pending.finish(result.decoded, ctx) {
+				Ok(value) => self.finished.borrow_mut().push(FinishedLoad { pending, generation: result.id, value }),
+				Err(error) => {
+					ctx.console.error(error.report());
+					pending.fail(result.id);
 				},
 			}
 		}
 	}
 
-	pub fn default<T: Resource>(&self, ctx: &EngineCtx) -> Res<T> {
-		let key = TypeId::of::<T>();
-		let existing = self.defaults.borrow().get(&key).map(|v| v.clone());
-		if let Some(v) = existing {
-			Res { inner: v.downcast::<qcell::TCell<ResourceMarker,T>>().unwrap() }
-		} else {
-			let v = T::default(ctx);
-			let arc = Arc::new(qcell::TCell::new(v));
-			self.defaults.borrow_mut().insert(key, arc.clone());
-			Res { inner: arc }
+	pub fn apply_loads(&mut self) {
+		for finished in self.finished.get_mut().drain(..) {
+			finished.pending.apply(finished.value, finished.generation, self.resource_owner.get_mut());
 		}
 	}
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	struct Text(&'static str);
+
+	impl Resource for Text {
+		type Decoded = ();
+
+		fn decode(_data: Vec<u8>) -> anyhow::Result<()> {
+			Ok(())
+		}
+
+		fn load(_decoded: (), _ctx: &EngineCtx) -> anyhow::Result<Self> {
+			unimplemented!()
+		}
+
+		fn default(_ctx: &EngineCtx) -> Self {
+			unimplemented!()
+		}
+	}
+
+	#[test]
+	fn out_of_order_loads() {
+		let mut owner = qcell::TCellOwner::<ResourceMarker>::wait_for_new();
+		let res = Res::new(Text("inserted"));
+		let load = TypedPendingLoad { path: "text.txt".to_owned(), target: Arc::downgrade(&res.inner), is_reload: false };
+		// a reload submitted after the initial load finishes first
+		load.reload().apply(Box::new(Text("reloaded")), 5, &mut owner);
+		load.apply(Box::new(Text("initial")), 2, &mut owner);
+		load.fail(3);
+		assert_eq!(res.inner.value.ro(&owner).as_ref().unwrap().0, "reloaded");
+		assert_eq!(res.load_state(), LoadState::Loaded);
+		load.reload().apply(Box::new(Text("reloaded again")), 6, &mut owner);
+		assert_eq!(res.inner.value.ro(&owner).as_ref().unwrap().0, "reloaded again");
+	}
+}
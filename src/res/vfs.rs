@@ -0,0 +1,330 @@
+use std::{path::PathBuf, sync::Arc, time::SystemTime};
+
+use rustc_hash::FxHashMap;
+
+/// A source of files that can be mounted into the resource filesystem with [`EngineCtx::mount`](crate::EngineCtx::mount).
+pub trait VfsMount: 'static + Send + Sync {
+	/// Reads the file at a normalized `path`.
+	///
+	/// Should return `Ok(None)` if the file does not exist in this mount so that lower priority mounts can be checked.
+	fn read(&self, path: &str) -> std::io::Result<Option<Vec<u8>>>;
+
+	/// Returns whether this mount has a file at a normalized `path`, and so is the one it will be read from unless a higher priority mount has it too.
+	///
+	/// The default implementation reads the file, so mounts should override it when they can check more cheaply.
+	fn contains(&self, path: &str) -> bool {
+		!matches!(self.read(path), Ok(None))
+	}
+
+	/// Returns the time the file at a normalized `path` was last modified, if it exists and can change.
+	///
+	/// Used to reload resources when their files change.
+	fn modified(&self, _path: &str) -> Option<SystemTime> {
+		None
+	}
+}
+
+/// Mounts a directory on disk.
+///
+/// The directory does not need to exist, which is useful for optional directories such as user mods.
+pub struct DirMount {
+	root: PathBuf,
+}
+
+impl DirMount {
+	pub fn new(root: impl Into<PathBuf>) -> Self {
+		Self { root: root.into() }
+	}
+}
+
+impl VfsMount for DirMount {
+	fn read(&self, path: &str) -> std::io::Result<Option<Vec<u8>>> {
+		match std::fs::read(self.root.join(path)) {
+			Ok(bytes) => Ok(Some(bytes)),
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+			Err(e) => Err(e),
+		}
+	}
+
+	fn contains(&self, path: &str) -> bool {
+		self.root.join(path).is_file()
+	}
+
+	fn modified(&self, path: &str) -> Option<SystemTime> {
+		std::fs::metadata(self.root.join(path)).and_then(|meta| meta.modified()).ok()
+	}
+}
+
+/// Mounts files that are embedded in the executable (usually with `include_bytes!`).
+#[derive(Default)]
+pub struct EmbeddedMount {
+	files: FxHashMap<String, &'static [u8]>,
+}
+
+impl EmbeddedMount {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a file to this mount.
+	///
+	/// Paths that are absolute or reference parent directories are ignored.
+	pub fn with(mut self, path: &str, bytes: &'static [u8]) -> Self {
+		self.insert(path, bytes);
+		self
+	}
+
+	/// Adds a file to this mount.
+	///
+	/// Paths that are absolute or reference parent directories are ignored.
+	pub fn insert(&mut self, path: &str, bytes: &'static [u8]) {
+		if let Some(path) = Vfs::normalize_path(path) {
+			self.files.insert(path, bytes);
+		}
+	}
+}
+
+impl VfsMount for EmbeddedMount {
+	fn read(&self, path: &str) -> std::io::Result<Option<Vec<u8>>> {
+		Ok(self.files.get(path).map(|bytes| bytes.to_vec()))
+	}
+
+	fn contains(&self, path: &str) -> bool {
+		self.files.contains_key(path)
+	}
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum VfsError {
+	#[error("invalid path \"{0}\"")]
+	InvalidPath(String),
+	#[error("file \"{0}\" not found in any mount")]
+	NotFound(String),
+	#[error("failed to read \"{path}\"")]
+	IoError {
+		path: String,
+		#[source]
+		source: std::io::Error,
+	},
+}
+
+/// Identifies a mount so that it can later be removed with [`EngineCtx::unmount`](crate::EngineCtx::unmount).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct MountID(usize);
+
+struct MountEntry {
+	id: MountID,
+	priority: i32,
+	mount: Arc<dyn VfsMount>,
+}
+
+/// Virtual filesystem that resource paths are resolved through.
+///
+/// Mounts are searched from highest to lowest priority, with more recent mounts winning ties.
+#[derive(Default)]
+pub(crate) struct Vfs {
+	mounts: Vec<MountEntry>,
+	next_id: usize,
+}
+
+impl Vfs {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn mount(&mut self, priority: i32, mount: Box<dyn VfsMount>) -> MountID {
+		let id = MountID(self.next_id);
+		self.next_id += 1;
+		let i = self.mounts.iter().position(|entry| entry.priority <= priority).unwrap_or(self.mounts.len());
+		self.mounts.insert(i, MountEntry { id, priority, mount: mount.into() });
+		id
+	}
+
+	pub fn unmount(&mut self, id: MountID) -> bool {
+		let len = self.mounts.len();
+		self.mounts.retain(|entry| entry.id != id);
+		self.mounts.len() != len
+	}
+
+	pub fn read(&self, path: &str) -> Result<Vec<u8>, VfsError> {
+		Self::read_from(&self.snapshot(), path)
+	}
+
+	/// Returns the mounts in the order they are searched.
+	///
+	/// Files can be read from the snapshot with [`read_from`](Vfs::read_from) without keeping the filesystem locked during I/O.
+	pub fn snapshot(&self) -> Vec<Arc<dyn VfsMount>> {
+		self.mounts.iter().map(|entry| entry.mount.clone()).collect()
+	}
+
+	pub fn read_from(mounts: &[Arc<dyn VfsMount>], path: &str) -> Result<Vec<u8>, VfsError> {
+		let normalized = Self::normalize_path(path).ok_or_else(|| VfsError::InvalidPath(path.to_owned()))?;
+		for mount in mounts {
+			let bytes = mount.read(&normalized).map_err(|source| VfsError::IoError { path: normalized.clone(), source })?;
+			if let Some(bytes) = bytes {
+				return Ok(bytes);
+			}
+		}
+		Err(VfsError::NotFound(normalized))
+	}
+
+	/// Returns the modification time of a file in the mount it is read from.
+	///
+	/// Files in lower priority mounts that are shadowed by it are ignored.
+	pub fn modified(&self, path: &str) -> Option<SystemTime> {
+		let normalized = Self::normalize_path(path)?;
+		self.mounts.iter().find(|entry| entry.mount.contains(&normalized))?.mount.modified(&normalized)
+	}
+
+	/// Resolves a `path` relative to the directory containing the file at `base`.
+	///
+	/// Unlike [`normalize_path`](Vfs::normalize_path), the path may reference parent directories as long as it doesn't leave the root.
+	pub fn resolve_relative(base: &str, path: &str) -> Option<String> {
+		if Self::is_absolute(path) {
+			return None;
+		}
+		let base = Self::normalize_path(base)?;
+		let mut parts = base.split('/').collect::<Vec<_>>();
+		parts.pop();
+		for part in path.split(['/', '\\']) {
+			match part {
+				"" | "." => {},
+				".." => {
+					parts.pop()?;
+				},
+				part => parts.push(part),
+			}
+		}
+		Self::normalize_path(&parts.join("/"))
+	}
+
+	fn is_absolute(path: &str) -> bool {
+		// absolute paths would escape directory mounts when joined onto their root, as would drive prefixes on Windows
+		let has_drive = matches!(path.as_bytes(), [drive, b':', ..] if drive.is_ascii_alphabetic());
+		path.starts_with(['/', '\\']) || has_drive
+	}
+
+	/// Converts a path into the form used to look up files in mounts.
+	///
+	/// Paths are relative, `/` separated and may not reference parent directories.
+	/// Returns `None` if the path is invalid.
+	pub fn normalize_path(path: &str) -> Option<String> {
+		if Self::is_absolute(path) {
+			return None;
+		}
+		let mut parts = vec![];
+		for part in path.split(['/', '\\']) {
+			match part {
+				"" | "." => {},
+				".." => return None,
+				part => parts.push(part),
+			}
+		}
+		if parts.is_empty() {
+			None
+		} else {
+			Some(parts.join("/"))
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn mount_priority() {
+		let mut vfs = Vfs::new();
+		vfs.mount(0, Box::new(EmbeddedMount::new().with("a.txt", b"base").with("b.txt", b"base")));
+		vfs.mount(10, Box::new(EmbeddedMount::new().with("a.txt", b"mod")));
+		vfs.mount(-10, Box::new(EmbeddedMount::new().with("b.txt", b"fallback").with("c.txt", b"fallback")));
+		assert_eq!(vfs.read("a.txt").unwrap(), b"mod");
+		assert_eq!(vfs.read("b.txt").unwrap(), b"base");
+		assert_eq!(vfs.read("c.txt").unwrap(), b"fallback");
+		// more recent mounts win ties
+		vfs.mount(0, Box::new(EmbeddedMount::new().with("b.txt", b"patch")));
+		assert_eq!(vfs.read("b.txt").unwrap(), b"patch");
+		assert!(matches!(vfs.read("d.txt"), Err(VfsError::NotFound(_))));
+	}
+
+	#[test]
+	fn unmount() {
+		let mut vfs = Vfs::new();
+		let base = vfs.mount(0, Box::new(EmbeddedMount::new().with("a.txt", b"base")));
+		let patch = vfs.mount(1, Box::new(EmbeddedMount::new().with("a.txt", b"patch")));
+		assert_eq!(vfs.read("a.txt").unwrap(), b"patch");
+		assert!(vfs.unmount(patch));
+		assert!(!vfs.unmount(patch));
+		assert_eq!(vfs.read("a.txt").unwrap(), b"base");
+		assert!(vfs.unmount(base));
+		assert!(matches!(vfs.read("a.txt"), Err(VfsError::NotFound(_))));
+	}
+
+	struct TimedMount {
+		path: &'static str,
+		modified: SystemTime,
+	}
+
+	impl VfsMount for TimedMount {
+		fn read(&self, path: &str) -> std::io::Result<Option<Vec<u8>>> {
+			Ok((path == self.path).then(Vec::new))
+		}
+
+		fn modified(&self, path: &str) -> Option<SystemTime> {
+			(path == self.path).then_some(self.modified)
+		}
+	}
+
+	#[test]
+	fn modified_from_serving_mount() {
+		let time = |secs| SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs);
+		let mut vfs = Vfs::new();
+		vfs.mount(0, Box::new(TimedMount { path: "a.txt", modified: time(100) }));
+		vfs.mount(1, Box::new(TimedMount { path: "a.txt", modified: time(10) }));
+		// the shadowed file being newer doesn't matter
+		assert_eq!(vfs.modified("a.txt"), Some(time(10)));
+		let embedded = vfs.mount(2, Box::new(EmbeddedMount::new().with("a.txt", b"")));
+		assert_eq!(vfs.modified("a.txt"), None);
+		vfs.unmount(embedded);
+		assert_eq!(vfs.modified("a.txt"), Some(time(10)));
+		assert_eq!(vfs.modified("b.txt"), None);
+	}
+
+	#[test]
+	fn resolve_relative() {
+		assert_eq!(Vfs::resolve_relative("models/chair.gltf", "chair.bin").as_deref(), Some("models/chair.bin"));
+		assert_eq!(Vfs::resolve_relative("models/chair.gltf", "./buffers\\chair.bin").as_deref(), Some("models/buffers/chair.bin"));
+		assert_eq!(Vfs::resolve_relative("models/chair.gltf", "../textures/wood.png").as_deref(), Some("textures/wood.png"));
+		assert_eq!(Vfs::resolve_relative("chair.gltf", "chair.bin").as_deref(), Some("chair.bin"));
+		assert_eq!(Vfs::resolve_relative("models/chair.gltf", "../../chair.bin"), None);
+		assert_eq!(Vfs::resolve_relative("models/chair.gltf", "/chair.bin"), None);
+	}
+
+	#[test]
+	fn normalize_path() {
+		assert_eq!(Vfs::normalize_path("a/b.txt").as_deref(), Some("a/b.txt"));
+		assert_eq!(Vfs::normalize_path("./a\\b//c.txt").as_deref(), Some("a/b/c.txt"));
+		assert_eq!(Vfs::normalize_path("/etc/passwd"), None);
+		assert_eq!(Vfs::normalize_path("\\\\server\\share"), None);
+		assert_eq!(Vfs::normalize_path("C:\\Windows"), None);
+		assert_eq!(Vfs::normalize_path("c:notes.txt"), None);
+		assert_eq!(Vfs::normalize_path("notes/10:30.txt").as_deref(), Some("notes/10:30.txt"));
+		assert_eq!(Vfs::normalize_path("a/../b.txt"), None);
+		assert_eq!(Vfs::normalize_path("../b.txt"), None);
+		assert_eq!(Vfs::normalize_path(""), None);
+		assert_eq!(Vfs::normalize_path("./"), None);
+		let mut vfs = Vfs::new();
+		vfs.mount(0, Box::new(EmbeddedMount::new().with("a.txt", b"a")));
+		assert!(matches!(vfs.read("../a.txt"), Err(VfsError::InvalidPath(_))));
+	}
+	#[test]
+	fn dir_mount_contains_files() {
+		let root = std::env::temp_dir().join(format!("isopod_dir_mount_test_{}", std::process::id()));
+		std::fs::create_dir_all(root.join("textures")).unwrap();
+		std::fs::write(root.join("a.txt"), b"a").unwrap();
+		let mount = DirMount::new(&root);
+		let contains = (mount.contains("a.txt"), mount.contains("textures"), mount.contains("b.txt"));
+		std::fs::remove_dir_all(&root).unwrap();
+		assert_eq!(contains, (true, false, false));
+	}
+}
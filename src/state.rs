@@ -0,0 +1,78 @@
+use crate::{App, EngineCtx};
+
+/// Change to the state stack requested through [`EngineCtx`], applied between frames.
+pub(crate) enum StateTransition {
+	Push(Box<dyn App>),
+	Pop,
+	Replace(Box<dyn App>),
+}
+
+/// States pushed on top of the [`App`] returned by the load function.
+///
+/// The root app is kept outside of the stack so it can never be popped and can be returned by [`run_headless`](crate::run_headless).
+#[derive(Default)]
+pub(crate) struct StateStack {
+	states: Vec<Box<dyn App>>,
+}
+
+impl StateStack {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Applies queued transitions, including any queued by the enter and exit hooks they trigger.
+	pub fn apply_transitions(&mut self, ctx: &mut EngineCtx) {
+		loop {
+			let transitions = std::mem::take(ctx.state_transitions.get_mut());
+			if transitions.is_empty() {
+				break;
+			}
+			for transition in transitions {
+				match transition {
+					StateTransition::Push(mut state) => {
+						state.on_enter(ctx);
+						self.states.push(state);
+					},
+					StateTransition::Pop => {
+						match self.states.pop() {
+							Some(mut state) => state.on_exit(ctx),
+							None => ctx.console.warn("tried to pop the root state"),
+						}
+					},
+					StateTransition::Replace(mut state) => {
+						if let Some(mut old) = self.states.pop() {
+							old.on_exit(ctx);
+						}
+						state.on_enter(ctx);
+						self.states.push(state);
+					},
+				}
+			}
+		}
+	}
+
+	/// Calls [`on_exit`](App::on_exit) on every state from top to bottom and then on the root app, when the application closes.
+	pub fn exit_all(&mut self, root: &mut dyn App, ctx: &EngineCtx) {
+		while let Some(mut state) = self.states.pop() {
+			state.on_exit(ctx);
+		}
+		root.on_exit(ctx);
+	}
+
+	/// Returns the root app followed by every state on the stack, from bottom to top.
+	pub fn layers<'a>(&'a mut self, root: &'a mut dyn App) -> Vec<&'a mut dyn App> {
+		let mut layers = Vec::with_capacity(self.states.len() + 1);
+		layers.push(root);
+		layers.extend(self.states.iter_mut().map(|state| state.as_mut() as &mut dyn App));
+		layers
+	}
+}
+
+/// Returns the index of the lowest layer that should be reached, given whether each layer lets the one below it through.
+pub(crate) fn lowest_layer(layers: &[&mut dyn App], passes_below: impl Fn(&dyn App) -> bool) -> usize {
+	let mut lowest = layers.len() - 1;
+	while lowest > 0 && passes_below(&*layers[lowest]) {
+		lowest -= 1;
+	}
+	lowest
+}
@@ -0,0 +1,240 @@
+use std::cell::Cell;
+
+/// Timing information for the current frame, accessed through [`EngineCtx::time`](crate::EngineCtx::time).
+///
+/// Scaled values are affected by the [time scale](Time::set_scale) and stop while [paused](Time::set_paused), which makes them suitable for gameplay.
+/// Unscaled values always follow real time, which is useful for menus and other things that should keep moving during slow motion or pauses.
+pub struct Time {
+	elapsed: f64,
+	unscaled_elapsed: f64,
+	dt: f64,
+	unscaled_dt: f64,
+	frame: u64,
+	scale: Cell<f64>,
+	paused: Cell<bool>,
+}
+
+impl Time {
+	pub(crate) fn new() -> Self {
+		Self {
+			elapsed: 0.,
+			unscaled_elapsed: 0.,
+			dt: 0.,
+			unscaled_dt: 0.,
+			frame: 0,
+			scale: Cell::new(1.),
+			paused: Cell::new(false),
+		}
+	}
+
+	/// Sets the time since the last frame for the first frame, which doesn't have a last frame to measure.
+	pub(crate) fn set_first_dt(&mut self, unscaled_dt: f64) {
+		self.unscaled_dt = unscaled_dt;
+		self.dt = if self.paused.get() { 0. } else { unscaled_dt * self.scale.get() };
+	}
+
+	/// Moves on to the next frame, given how long the last one took in real time.
+	pub(crate) fn advance(&mut self, unscaled_dt: f64) {
+		self.unscaled_dt = unscaled_dt;
+		self.dt = if self.paused.get() { 0. } else { unscaled_dt * self.scale.get() };
+		self.unscaled_elapsed += self.unscaled_dt;
+		self.elapsed += self.dt;
+		self.frame += 1;
+	}
+
+	/// Scaled time (in seconds) since the last frame.
+	pub fn dt(&self) -> f64 {
+		self.dt
+	}
+
+	/// Real time (in seconds) since the last frame.
+	pub fn unscaled_dt(&self) -> f64 {
+		self.unscaled_dt
+	}
+
+	/// Scaled time (in seconds) since the game started.
+	pub fn elapsed(&self) -> f64 {
+		self.elapsed
+	}
+
+	/// Real time (in seconds) since the game started.
+	pub fn unscaled_elapsed(&self) -> f64 {
+		self.unscaled_elapsed
+	}
+
+	/// Number of frames that have been completed, starting at 0 for the first frame.
+	pub fn frame(&self) -> u64 {
+		self.frame
+	}
+
+	pub fn scale(&self) -> f64 {
+		self.scale.get()
+	}
+
+	/// Sets how fast scaled time moves relative to real time, such as `0.25` for slow motion.
+	///
+	/// This also affects how often [`fixed_update`](crate::App::fixed_update) is called.
+	/// Takes effect from the next frame on.
+	pub fn set_scale(&self, scale: f64) {
+		self.scale.set(scale.max(0.));
+	}
+
+	pub fn is_paused(&self) -> bool {
+		self.paused.get()
+	}
+
+	/// Stops scaled time (and [`fixed_update`](crate::App::fixed_update) calls) while `true`.
+	///
+	/// Takes effect from the next frame on.
+	pub fn set_paused(&self, paused: bool) {
+		self.paused.set(paused);
+	}
+}
+
+/// Timer that fires after a duration, either once or repeatedly.
+///
+/// Timers are owned by the game and advanced by calling [`tick`](Timer::tick) once per [update](crate::App::update).
+#[derive(Clone, Debug)]
+pub struct Timer {
+	duration: f64,
+	elapsed: f64,
+	repeating: bool,
+	unscaled: bool,
+	finished: bool,
+}
+
+impl Timer {
+	/// Creates a timer that fires once after `duration` seconds.
+	pub fn once(duration: f64) -> Self {
+		Self { duration, elapsed: 0., repeating: false, unscaled: false, finished: false }
+	}
+
+	/// Creates a timer that fires every `interval` seconds.
+	pub fn repeating(interval: f64) -> Self {
+		Self { duration: interval, elapsed: 0., repeating: true, unscaled: false, finished: false }
+	}
+
+	/// Makes this timer follow real time instead of scaled time.
+	pub fn unscaled(mut self) -> Self {
+		self.unscaled = true;
+		self
+	}
+
+	/// Advances this timer by the time since the last frame and returns how many times it fired.
+	///
+	/// Repeating timers fire once for every interval that passed, which can be more than once if the interval is shorter than a frame.
+	/// They keep any leftover time so that they don't drift.
+	/// Repeating timers with an interval of 0 fire once per call.
+	pub fn tick(&mut self, time: &Time) -> u32 {
+		if self.finished {
+			return 0;
+		}
+		self.elapsed += if self.unscaled { time.unscaled_dt() } else { time.dt() };
+		if self.elapsed < self.duration {
+			return 0;
+		}
+		if !self.repeating {
+			self.elapsed = self.duration;
+			self.finished = true;
+			return 1;
+		}
+		if self.duration <= 0. {
+			self.elapsed = 0.;
+			return 1;
+		}
+		let fired = (self.elapsed / self.duration).floor();
+		self.elapsed -= fired * self.duration;
+		fired as u32
+	}
+
+	/// Starts the timer again from zero.
+	pub fn reset(&mut self) {
+		self.elapsed = 0.;
+		self.finished = false;
+	}
+
+	/// Returns `true` once a one-shot timer has fired.
+	pub fn is_finished(&self) -> bool {
+		self.finished
+	}
+
+	/// Time (in seconds) until the timer next fires.
+	pub fn remaining(&self) -> f64 {
+		(self.duration - self.elapsed).max(0.)
+	}
+
+	/// Progress (from 0 to 1) towards the timer next firing, which can be used to drive tweens.
+	pub fn fraction(&self) -> f64 {
+		if self.duration > 0. {
+			(self.elapsed / self.duration).min(1.)
+		} else {
+			1.
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn frame(time: &mut Time, dt: f64) -> &Time {
+		time.advance(dt);
+		time
+	}
+
+	#[test]
+	fn scale_and_pause() {
+		let mut time = Time::new();
+		time.set_scale(0.5);
+		frame(&mut time, 0.2);
+		assert_eq!(time.dt(), 0.1);
+		assert_eq!(time.unscaled_dt(), 0.2);
+		time.set_paused(true);
+		frame(&mut time, 0.2);
+		assert_eq!(time.dt(), 0.);
+		assert_eq!(time.unscaled_dt(), 0.2);
+		assert_eq!(time.elapsed(), 0.1);
+		assert!((time.unscaled_elapsed() - 0.4).abs() < 1e-9);
+		assert_eq!(time.frame(), 2);
+		time.set_scale(-1.);
+		assert_eq!(time.scale(), 0.);
+	}
+
+	#[test]
+	fn once() {
+		let mut time = Time::new();
+		let mut timer = Timer::once(1.);
+		assert_eq!(timer.tick(frame(&mut time, 0.75)), 0);
+		assert_eq!(timer.fraction(), 0.75);
+		assert_eq!(timer.tick(frame(&mut time, 0.75)), 1);
+		assert!(timer.is_finished());
+		assert_eq!(timer.remaining(), 0.);
+		assert_eq!(timer.tick(frame(&mut time, 5.)), 0);
+		timer.reset();
+		assert!(!timer.is_finished());
+		assert_eq!(timer.tick(frame(&mut time, 1.)), 1);
+	}
+
+	#[test]
+	fn repeating() {
+		let mut time = Time::new();
+		let mut timer = Timer::repeating(0.5);
+		assert_eq!(timer.tick(frame(&mut time, 0.75)), 1);
+		assert_eq!(timer.remaining(), 0.25);
+		// a long frame fires once for every interval that passed
+		assert_eq!(timer.tick(frame(&mut time, 1.5)), 3);
+		assert_eq!(timer.remaining(), 0.25);
+		assert!(!timer.is_finished());
+	}
+
+	#[test]
+	fn unscaled() {
+		let mut time = Time::new();
+		time.set_paused(true);
+		let mut scaled = Timer::once(1.);
+		let mut unscaled = Timer::once(1.).unscaled();
+		frame(&mut time, 1.);
+		assert_eq!(scaled.tick(&time), 0);
+		assert_eq!(unscaled.tick(&time), 1);
+	}
+}
@@ -42,14 +42,15 @@ impl<T> BufferDequeCell<T> {
 		Self {inner: UnsafeCell::new(VecDeque::new())}
 	}
 
-	pub fn get_mut(&mut self) -> &mut VecDeque<T> {
-		self.inner.get_mut()
-	}
-
 	pub fn push(&self, v: T) {
 		let inner = unsafe {&mut *self.inner.get()};
 		inner.push_back(v);
 	}
+
+	pub fn take(&self) -> VecDeque<T> {
+		let inner = unsafe {&mut *self.inner.get()};
+		std::mem::take(inner)
+	}
 }
 
 
@@ -228,4 +229,13 @@ pub const fn align_up(v: usize, multiple: usize) -> usize {
 			v + multiple - remainder
 		}
 	}
+}
+
+/// Removes module paths from a type name, so `a::B<c::D>` becomes `B<D>`.
+pub(crate) fn short_type_name(name: &str) -> String {
+	let mut short = String::new();
+	for part in name.split_inclusive(['<', '>', ',', ' ', '(', ')', '[', ']', ';', '&']) {
+		short += part.rsplit("::").next().unwrap_or(part);
+	}
+	short
 }
\ No newline at end of file
@@ -0,0 +1,723 @@
+use isopod::{input::{ButtonMapping, Key}, sdl2, App, EngineCtx};
+
+struct Jump;
+
+#[derive(Default)]
+struct Game {
+	updates: usize,
+	ticks: usize,
+	jumps: usize,
+}
+
+impl App for Game {
+	fn update(&mut self, c: &EngineCtx) {
+		assert_eq!(c.dt, isopod::HEADLESS_DT);
+		assert_eq!(c.time.dt(), isopod::HEADLESS_DT);
+		self.updates += 1;
+		if c.input.is_just_pressed::<Jump>() {
+			self.jumps += 1;
+		}
+		if self.updates == 5 {
+			c.input.inject_event(sdl2::event::Event::KeyDown {
+				timestamp: 0,
+				window_id: 0,
+				keycode: None,
+				scancode: Some(sdl2::keyboard::Scancode::Space),
+				keymod: sdl2::keyboard::Mod::NOMOD,
+				repeat: false,
+			});
+		}
+		if self.updates == 20 {
+			c.quit();
+		}
+	}
+
+	fn fixed_update(&mut self, _c: &EngineCtx) {
+		self.ticks += 1;
+	}
+}
+
+#[test]
+fn headless_run() {
+	let game = isopod::run_headless(100, |c| {
+		c.input.map_button::<Jump>(ButtonMapping::LogicalKey(Key::SPACE));
+		c.set_tick_rate(30.);
+		Game::default()
+	});
+	assert_eq!(game.updates, 20);
+	assert_eq!(game.jumps, 1);
+	assert!((9..=10).contains(&game.ticks));
+}
+
+struct Paused {
+	frames: std::rc::Rc<std::cell::Cell<usize>>,
+}
+
+impl App for Paused {
+	fn update(&mut self, c: &EngineCtx) {
+		self.frames.set(self.frames.get() + 1);
+		if self.frames.get() == 10 {
+			c.pop_state();
+		}
+	}
+
+	fn on_exit(&mut self, c: &EngineCtx) {
+		c.quit();
+	}
+}
+
+#[derive(Default)]
+struct Root {
+	updates: usize,
+	paused_frames: std::rc::Rc<std::cell::Cell<usize>>,
+}
+
+impl App for Root {
+	fn update(&mut self, c: &EngineCtx) {
+		self.updates += 1;
+		if self.updates == 5 {
+			c.push_state(Paused { frames: self.paused_frames.clone() });
+		}
+	}
+}
+
+#[test]
+fn state_stack() {
+	let game = isopod::run_headless(100, |_| Root::default());
+	assert_eq!(game.updates, 5);
+	assert_eq!(game.paused_frames.get(), 10);
+}
+
+/// Records the order hooks are called in.
+type CallLog = std::rc::Rc<std::cell::RefCell<Vec<&'static str>>>;
+
+struct ExitLog {
+	name: &'static str,
+	log: CallLog,
+}
+
+impl App for ExitLog {
+	fn update(&mut self, c: &EngineCtx) {
+		if self.name == "root" {
+			c.push_state(ExitLog { name: "state", log: self.log.clone() });
+		}
+	}
+
+	fn update_below(&self) -> bool {
+		true
+	}
+
+	fn on_exit(&mut self, _c: &EngineCtx) {
+		self.log.borrow_mut().push(self.name);
+	}
+}
+
+#[test]
+fn exit_on_shutdown() {
+	let log = CallLog::default();
+	isopod::run_headless(1, |_| ExitLog { name: "root", log: log.clone() });
+	assert_eq!(*log.borrow(), ["state", "root"]);
+}
+
+/// Refuses to quit, and pushes another state that does the same on the first frame.
+struct Stubborn {
+	asked: CallLog,
+	name: &'static str,
+}
+
+impl App for Stubborn {
+	fn update(&mut self, c: &EngineCtx) {
+		if self.name == "root" && self.asked.borrow().is_empty() {
+			c.push_state(Stubborn { asked: self.asked.clone(), name: "state" });
+			c.input.inject_event(sdl2::event::Event::Quit { timestamp: 0 });
+		}
+	}
+
+	fn update_below(&self) -> bool {
+		true
+	}
+
+	fn on_quit_requested(&mut self, _c: &EngineCtx) -> bool {
+		self.asked.borrow_mut().push(self.name);
+		false
+	}
+}
+
+#[test]
+fn quit_requests_ask_every_state() {
+	let asked = CallLog::default();
+	isopod::run_headless(3, |_| Stubborn { asked: asked.clone(), name: "root" });
+	assert_eq!(*asked.borrow(), ["state", "root"]);
+}
+
+struct LogPlugin {
+	log: CallLog,
+}
+
+impl isopod::Plugin for LogPlugin {
+	fn build(&mut self, _c: &EngineCtx) {
+		self.log.borrow_mut().push("build");
+	}
+
+	fn pre_update(&mut self, _c: &EngineCtx) {
+		self.log.borrow_mut().push("pre_update");
+	}
+
+	fn post_update(&mut self, _c: &EngineCtx) {
+		self.log.borrow_mut().push("post_update");
+	}
+}
+
+struct LogApp {
+	log: CallLog,
+}
+
+impl App for LogApp {
+	fn update(&mut self, _c: &EngineCtx) {
+		self.log.borrow_mut().push("update");
+	}
+}
+
+#[test]
+fn plugins() {
+	let log = CallLog::default();
+	isopod::run_headless(2, |c| {
+		c.add_plugin(LogPlugin { log: log.clone() });
+		LogApp { log: log.clone() }
+	});
+	assert_eq!(*log.borrow(), ["build", "pre_update", "update", "post_update", "pre_update", "update", "post_update"]);
+}
+
+struct EventCounter {
+	key_downs: std::rc::Rc<std::cell::Cell<usize>>,
+}
+
+impl isopod::Plugin for EventCounter {
+	fn on_event(&mut self, _c: &EngineCtx, event: &sdl2::event::Event) {
+		if let sdl2::event::Event::KeyDown { .. } = event {
+			self.key_downs.set(self.key_downs.get() + 1);
+		}
+	}
+}
+
+#[test]
+fn plugins_get_injected_events() {
+	let key_downs = std::rc::Rc::new(std::cell::Cell::new(0));
+	isopod::run_headless(10, |c| {
+		c.add_plugin(EventCounter { key_downs: key_downs.clone() });
+		Game::default()
+	});
+	assert_eq!(key_downs.get(), 1);
+}
+
+struct Gamepads {
+	frames: usize,
+	jumps: usize,
+	player_one_jumps: usize,
+}
+
+impl App for Gamepads {
+	fn update(&mut self, c: &EngineCtx) {
+		use sdl2::{controller::Button, event::Event};
+		self.frames += 1;
+		if c.input.is_just_pressed::<Jump>() {
+			self.jumps += 1;
+		}
+		if c.input.is_gamepad_just_pressed(1, isopod::input::GamepadButton::A) {
+			self.player_one_jumps += 1;
+		}
+		let press = |which, pressed| c.input.inject_event(if pressed {
+			Event::ControllerButtonDown { timestamp: 0, which, button: Button::A }
+		} else {
+			Event::ControllerButtonUp { timestamp: 0, which, button: Button::A }
+		});
+		match self.frames {
+			// both gamepads hold the button at once, which is only one press of the action
+			2 => press(10, true),
+			3 => press(20, true),
+			4 => press(10, false),
+			5 => press(20, false),
+			6 => press(20, true),
+			7 => c.input.inject_event(Event::ControllerDeviceRemoved { timestamp: 0, which: 20 }),
+			8 => c.quit(),
+			_ => {},
+		}
+	}
+}
+
+#[test]
+fn gamepads() {
+	let game = isopod::run_headless(100, |c| {
+		c.input.map_button::<Jump>(ButtonMapping::Gamepad(isopod::input::GamepadButton::A));
+		Gamepads { frames: 0, jumps: 0, player_one_jumps: 0 }
+	});
+	assert_eq!(game.jumps, 2);
+	assert_eq!(game.player_one_jumps, 2);
+}
+
+struct Move;
+
+#[derive(Default)]
+struct Mover {
+	frames: usize,
+	directions: Vec<isopod::math::Vec2>,
+}
+
+impl App for Mover {
+	fn update(&mut self, c: &EngineCtx) {
+		use sdl2::{event::Event, keyboard::{Mod, Scancode}};
+		self.frames += 1;
+		self.directions.push(c.input.axis2d::<Move>());
+		let key_down = |scancode| Event::KeyDown {
+			timestamp: 0, window_id: 0, keycode: None, scancode: Some(scancode), keymod: Mod::NOMOD, repeat: false,
+		};
+		match self.frames {
+			1 => c.input.inject_event(key_down(Scancode::D)),
+			2 => c.input.inject_event(key_down(Scancode::W)),
+			3 => c.quit(),
+			_ => {},
+		}
+	}
+}
+
+#[test]
+fn axis_composite() {
+	use isopod::input::{AxisMapping, AxisSource};
+	let game = isopod::run_headless(100, |c| {
+		c.input.map_axis::<Move>(AxisMapping {
+			invert_y: true,
+			..AxisMapping::new(AxisSource::Buttons2D {
+				up: ButtonMapping::LogicalKey(Key::W),
+				down: ButtonMapping::LogicalKey(Key::S),
+				left: ButtonMapping::LogicalKey(Key::A),
+				right: ButtonMapping::LogicalKey(Key::D),
+			})
+		});
+		Mover::default()
+	});
+	assert_eq!(game.directions[0], isopod::math::Vec2::ZERO);
+	assert_eq!(game.directions[1], isopod::math::Vec2::X);
+	assert!((game.directions[2] - isopod::math::Vec2::ONE.normalize()).length() < 1e-5);
+}
+
+/// Holds D and space, then loses focus without releasing them.
+#[derive(Default)]
+struct Unfocused {
+	frames: usize,
+	directions: Vec<isopod::math::Vec2>,
+	jumping: Vec<bool>,
+}
+
+impl App for Unfocused {
+	fn update(&mut self, c: &EngineCtx) {
+		use sdl2::{event::{Event, WindowEvent}, keyboard::{Mod, Scancode}};
+		self.frames += 1;
+		self.directions.push(c.input.axis2d::<Move>());
+		self.jumping.push(c.input.is_pressed::<Jump>());
+		let key_down = |scancode| Event::KeyDown {
+			timestamp: 0, window_id: 0, keycode: None, scancode: Some(scancode), keymod: Mod::NOMOD, repeat: false,
+		};
+		match self.frames {
+			1 => {
+				c.input.inject_event(key_down(Scancode::D));
+				c.input.inject_event(key_down(Scancode::Space));
+			},
+			2 => c.input.inject_event(Event::Window { timestamp: 0, window_id: 0, win_event: WindowEvent::FocusLost }),
+			3 => c.quit(),
+			_ => {},
+		}
+	}
+}
+
+#[test]
+fn focus_loss_releases_input() {
+	use isopod::{input::{AxisMapping, AxisSource}, math::Vec2};
+	let game = isopod::run_headless(100, |c| {
+		c.input.map_axis::<Move>(AxisMapping::new(AxisSource::Buttons {
+			negative: ButtonMapping::LogicalKey(Key::A),
+			positive: ButtonMapping::LogicalKey(Key::D),
+		}));
+		c.input.map_button::<Jump>(ButtonMapping::LogicalKey(Key::SPACE));
+		Unfocused::default()
+	});
+	assert_eq!(game.directions, [Vec2::ZERO, Vec2::X, Vec2::ZERO]);
+	assert_eq!(game.jumping, [false, true, false]);
+}
+
+struct Save;
+
+#[derive(Default)]
+struct Editor {
+	frames: usize,
+	saves: usize,
+}
+
+impl App for Editor {
+	fn update(&mut self, c: &EngineCtx) {
+		use sdl2::{event::Event, keyboard::{Keycode, Mod}};
+		self.frames += 1;
+		if c.input.is_just_pressed::<Save>() {
+			self.saves += 1;
+		}
+		let key = |keycode, keymod, down| if down {
+			Event::KeyDown { timestamp: 0, window_id: 0, keycode: Some(keycode), scancode: None, keymod, repeat: false }
+		} else {
+			Event::KeyUp { timestamp: 0, window_id: 0, keycode: Some(keycode), scancode: None, keymod, repeat: false }
+		};
+		match self.frames {
+			1 => c.input.inject_event(key(Keycode::S, Mod::NOMOD, true)),
+			2 => c.input.inject_event(key(Keycode::S, Mod::NOMOD, false)),
+			3 => c.input.inject_event(key(Keycode::S, Mod::LCTRLMOD | Mod::LSHIFTMOD, true)),
+			4 => c.input.inject_event(key(Keycode::S, Mod::LCTRLMOD | Mod::LSHIFTMOD, false)),
+			5 => c.input.inject_event(key(Keycode::S, Mod::RCTRLMOD, true)),
+			6 => {
+				assert!(c.input.modifiers().ctrl());
+				c.quit();
+			},
+			_ => {},
+		}
+	}
+}
+
+#[test]
+fn shortcuts() {
+	let game = isopod::run_headless(100, |c| {
+		c.input.map_button::<Save>(ButtonMapping::Shortcut(isopod::input::Modifiers::CTRL, Key::S));
+		Editor::default()
+	});
+	assert_eq!(game.saves, 1);
+}
+
+#[derive(Default)]
+struct ShortcutMenu {
+	frames: usize,
+	heard: Vec<Option<ButtonMapping>>,
+}
+
+impl App for ShortcutMenu {
+	fn update(&mut self, c: &EngineCtx) {
+		use sdl2::{event::Event, keyboard::{Keycode, Mod, Scancode}};
+		self.frames += 1;
+		self.heard.push(c.input.heard_input());
+		let key = |keycode, scancode, keymod, down| if down {
+			Event::KeyDown { timestamp: 0, window_id: 0, keycode: Some(keycode), scancode: Some(scancode), keymod, repeat: false }
+		} else {
+			Event::KeyUp { timestamp: 0, window_id: 0, keycode: Some(keycode), scancode: Some(scancode), keymod, repeat: false }
+		};
+		match self.frames {
+			1 => {
+				c.input.listen_for_input();
+				c.input.inject_event(key(Keycode::LCtrl, Scancode::LCtrl, Mod::LCTRLMOD, true));
+			},
+			2 => {
+				assert!(c.input.is_listening());
+				c.input.inject_event(key(Keycode::S, Scancode::S, Mod::LCTRLMOD, true));
+			},
+			3 => {
+				c.input.inject_event(key(Keycode::S, Scancode::S, Mod::LCTRLMOD, false));
+				c.input.inject_event(key(Keycode::LCtrl, Scancode::LCtrl, Mod::NOMOD, false));
+			},
+			4 => {
+				c.input.listen_for_input();
+				c.input.inject_event(key(Keycode::LShift, Scancode::LShift, Mod::LSHIFTMOD, true));
+			},
+			5 => c.input.inject_event(key(Keycode::LShift, Scancode::LShift, Mod::NOMOD, false)),
+			6 => c.quit(),
+			_ => {},
+		}
+	}
+}
+
+#[test]
+fn listening_for_shortcuts() {
+	let game = isopod::run_headless(100, |_| ShortcutMenu::default());
+	let shortcut = ButtonMapping::Shortcut(isopod::input::Modifiers::CTRL, Key::S);
+	// a modifier released on its own is heard as a key, but not if it was part of a shortcut
+	assert_eq!(game.heard, [None, None, Some(shortcut), None, None, Some(ButtonMapping::LogicalKey(Key::LSHIFT))]);
+}
+
+#[derive(Default)]
+struct ControlsMenu {
+	frames: usize,
+	saved: Option<String>,
+	rebound: Vec<ButtonMapping>,
+	loaded: Vec<ButtonMapping>,
+}
+
+impl App for ControlsMenu {
+	fn update(&mut self, c: &EngineCtx) {
+		use sdl2::{event::Event, keyboard::{Mod, Scancode}};
+		self.frames += 1;
+		match self.frames {
+			1 => {
+				c.input.listen_for_input();
+				c.input.inject_event(Event::KeyDown {
+					timestamp: 0, window_id: 0, keycode: None, scancode: Some(Scancode::J), keymod: Mod::NOMOD, repeat: false,
+				});
+			},
+			2 => {
+				c.input.rebind::<Jump>(ButtonMapping::LogicalKey(Key::SPACE), c.input.heard_input().unwrap());
+				// K isn't mapped to Jump, so this does nothing
+				c.input.rebind::<Jump>(ButtonMapping::LogicalKey(Key::K), ButtonMapping::LogicalKey(Key::L));
+			},
+			3 => {
+				self.rebound = c.input.button_bindings::<Jump>().to_vec();
+				self.saved = Some(c.input.bindings().to_ron().unwrap());
+				c.input.unbind::<Jump>();
+			},
+			4 => c.input.load_bindings(isopod::input::Bindings::from_ron(self.saved.as_ref().unwrap()).unwrap()),
+			5 => {
+				self.loaded = c.input.button_bindings::<Jump>().to_vec();
+				c.quit();
+			},
+			_ => {},
+		}
+	}
+}
+
+#[test]
+fn rebinding() {
+	let game = isopod::run_headless(100, |c| {
+		c.input.map_button::<Jump>(ButtonMapping::LogicalKey(Key::SPACE));
+		c.input.map_button::<Jump>(ButtonMapping::Gamepad(isopod::input::GamepadButton::A));
+		c.input.name_action::<Jump>("jump");
+		ControlsMenu::default()
+	});
+	let expected = vec![ButtonMapping::LogicalKey(Key::J), ButtonMapping::Gamepad(isopod::input::GamepadButton::A)];
+	assert_eq!(game.rebound, expected);
+	assert!(game.saved.unwrap().contains("\"jump\""));
+	assert_eq!(game.loaded, expected);
+}
+
+mod ui {
+	pub struct Confirm;
+}
+
+mod game {
+	pub struct Confirm;
+}
+
+#[derive(Default)]
+struct DuplicateNames {
+	bindings: Vec<isopod::input::Bindings>,
+}
+
+impl App for DuplicateNames {
+	fn update(&mut self, c: &EngineCtx) {
+		self.bindings.push(c.input.bindings());
+		if self.bindings.len() == 1 {
+			c.input.name_action::<game::Confirm>("Confirm");
+		} else {
+			c.quit();
+		}
+	}
+}
+
+#[test]
+fn duplicate_action_names() {
+	let game = isopod::run_headless(100, |c| {
+		c.input.map_button::<ui::Confirm>(ButtonMapping::LogicalKey(Key::RETURN));
+		c.input.map_button::<game::Confirm>(ButtonMapping::LogicalKey(Key::E));
+		DuplicateNames::default()
+	});
+	for bindings in &game.bindings {
+		assert_eq!(bindings.buttons["Confirm"], [ButtonMapping::LogicalKey(Key::RETURN)]);
+		assert_eq!(bindings.buttons["headless::game::Confirm"], [ButtonMapping::LogicalKey(Key::E)]);
+	}
+}
+
+static NOTE_DEFAULTS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+struct Note(String);
+
+impl isopod::Resource for Note {
+	type Decoded = String;
+
+	fn decode(data: Vec<u8>) -> anyhow::Result<String> {
+		Ok(String::from_utf8(data)?)
+	}
+
+	fn load(decoded: String, _ctx: &EngineCtx) -> anyhow::Result<Self> {
+		Ok(Note(decoded))
+	}
+
+	fn default(_ctx: &EngineCtx) -> Self {
+		NOTE_DEFAULTS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+		Note("default".to_owned())
+	}
+}
+
+struct Notes {
+	notes: Vec<isopod::Res<Note>>,
+	written: bool,
+}
+
+impl App for Notes {
+	fn update(&mut self, c: &EngineCtx) {
+		if self.notes.iter().all(|note| note.load_state() == isopod::LoadState::Failed) {
+			c.write(&self.notes[0]).0 = "written".to_owned();
+			self.written = true;
+			assert_eq!(c.read(&self.notes[1]).0, "default");
+			c.quit();
+		}
+	}
+}
+
+#[test]
+fn shared_defaults() {
+	let game = isopod::run_headless(1000, |c| {
+		let notes = (0..3).map(|i| c.load::<Note>(format!("missing_{}.txt", i))).collect::<Vec<_>>();
+		assert_eq!(c.read(&notes[2]).0, "default");
+		Notes { notes, written: false }
+	});
+	assert!(game.written);
+	// one shared default, plus a copy for the note that was written to
+	assert_eq!(NOTE_DEFAULTS.load(std::sync::atomic::Ordering::Relaxed), 2);
+}
+
+/// Like [`Note`], but not counted, since writing to it while it loads creates a default.
+struct Memo(String);
+
+impl isopod::Resource for Memo {
+	type Decoded = String;
+
+	fn decode(data: Vec<u8>) -> anyhow::Result<String> {
+		Ok(String::from_utf8(data)?)
+	}
+
+	fn load(decoded: String, _ctx: &EngineCtx) -> anyhow::Result<Self> {
+		Ok(Memo(decoded))
+	}
+
+	fn default(_ctx: &EngineCtx) -> Self {
+		Memo("default".to_owned())
+	}
+}
+
+struct EditedNote {
+	note: isopod::Res<Memo>,
+	frames: usize,
+	text: String,
+}
+
+impl App for EditedNote {
+	fn update(&mut self, c: &EngineCtx) {
+		self.frames += 1;
+		self.text = c.read(&self.note).0.clone();
+		if self.frames == 50 {
+			c.quit();
+		}
+	}
+}
+
+#[test]
+fn writes_cancel_loads() {
+	let game = isopod::run_headless(100, |c| {
+		c.mount(0, isopod::EmbeddedMount::new().with("note.txt", b"loaded"));
+		let note = c.load::<Memo>("note.txt");
+		{
+			let _reading = c.read(&note);
+			assert!(c.try_write(&note).is_none());
+		}
+		c.write(&note).0 = "edited".to_owned();
+		EditedNote { note, frames: 0, text: String::new() }
+	});
+	assert_eq!(game.text, "edited");
+	assert_eq!(game.note.load_state(), isopod::LoadState::Loaded);
+}
+
+#[test]
+fn try_load_unloaded() {
+	let game = isopod::run_headless(100, |c| {
+		c.mount(0, isopod::EmbeddedMount::new().with("note.txt", b"loaded"));
+		let note = c.load::<Memo>("note.txt");
+		let loaded = c.try_load::<Memo>("note.txt").unwrap();
+		assert_eq!(c.read(&loaded).0, "loaded");
+		let missing = c.load::<Memo>("missing.txt");
+		assert!(c.try_load::<Memo>("missing.txt").is_err());
+		assert_eq!(missing.load_state(), isopod::LoadState::Failed);
+		// still failed, so the error is returned again
+		let error = c.try_load::<Memo>("missing.txt").err().unwrap();
+		assert!(error.report().contains("missing.txt"));
+		EditedNote { note, frames: 0, text: String::new() }
+	});
+	assert_eq!(game.text, "loaded");
+	assert_eq!(game.note.load_state(), isopod::LoadState::Loaded);
+}
+
+const TRIANGLE_GLTF: &str = r#"{
+	"asset": {"version": "2.0"},
+	"scene": 0,
+	"scenes": [{"nodes": [0]}],
+	"nodes": [{"mesh": 0}],
+	"meshes": [{"primitives": [{"attributes": {"POSITION": 0}}]}],
+	"buffers": [{"uri": "../buffers/triangle.bin", "byteLength": 36}],
+	"bufferViews": [{"buffer": 0, "byteLength": 36}],
+	"accessors": [{"bufferView": 0, "componentType": 5126, "type": "VEC3", "count": 3}]
+}"#;
+
+#[test]
+fn gltf_external_buffers() {
+	let positions = [[0f32, 0., 0.], [1., 0., 0.], [0., 1., 0.]];
+	let buffer = positions.iter().flatten().flat_map(|v| v.to_le_bytes()).collect::<Vec<_>>();
+	isopod::run_headless(1, |c| {
+		c.mount(0, isopod::EmbeddedMount::new()
+			.with("models/triangle.gltf", TRIANGLE_GLTF.as_bytes())
+			.with("buffers/triangle.bin", buffer.leak()));
+		let scene = c.try_load::<isopod::gltf::GltfDecoded>("models/triangle.gltf").unwrap();
+		let scene = c.read(&scene);
+		let mesh = scene.nodes.iter().next().unwrap().mesh.as_ref().unwrap();
+		assert_eq!(mesh.primitives[0].positions, positions);
+		Game::default()
+	});
+}
+
+#[test]
+fn console_non_ascii() {
+	isopod::run_headless(3, |c| {
+		c.console.error("failed to load \"textures/café.png\": файл не найден ✗");
+		Game::default()
+	});
+}
+
+#[derive(Default)]
+struct FrameRecorder {
+	frames: Vec<isopod::gfx::HeadlessFrame>,
+}
+
+impl App for FrameRecorder {
+	fn update(&mut self, c: &EngineCtx) {
+		self.frames.push(c.headless_frame().unwrap().clone());
+	}
+}
+
+#[test]
+fn headless_frames() {
+	use isopod::gfx::{HeadlessDrawCmd, HeadlessResourceKind, HeadlessResourceUpdate};
+	let game = isopod::run_headless(3, |_| FrameRecorder::default());
+	// the console creates its resources before the first frame and draws itself every frame
+	let created = |kind| game.frames[0].resource_updates.iter().any(|update| matches!(update, HeadlessResourceUpdate::Create { kind: k, .. } if *k == kind));
+	assert!(created(HeadlessResourceKind::Shader));
+	assert!(created(HeadlessResourceKind::Texture2D));
+	assert!(created(HeadlessResourceKind::Sampler));
+	assert!(game.frames[1].resource_updates.is_empty());
+	let draw_cmds = &game.frames[1].draw_cmds;
+	assert!(matches!(draw_cmds[0], HeadlessDrawCmd::SetCanvas { framebuffer: None, .. }));
+	assert!(draw_cmds.iter().any(|cmd| matches!(cmd, HeadlessDrawCmd::DrawMesh { elements, instances: 1 } if *elements > 0)));
+}
+
+#[test]
+fn config_io_errors() {
+	let err = isopod::RunConfig::from_file("missing/settings.ron").unwrap_err();
+	assert!(matches!(&err, isopod::ConfigError::IoError { path, .. } if path.ends_with("settings.ron")));
+	assert!(err.to_string().contains("missing/settings.ron"));
+	assert!(std::error::Error::source(&err).is_some());
+}
+
+#[test]
+fn bindings_errors() {
+	let err = isopod::input::Bindings::from_file("missing/controls.ron").unwrap_err();
+	assert!(err.to_string().contains("missing/controls.ron"));
+	let err = isopod::input::Bindings::from_ron("(buttons: 5)").unwrap_err();
+	assert_eq!(err.to_string(), "failed to parse bindings");
+}